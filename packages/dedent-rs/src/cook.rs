@@ -0,0 +1,690 @@
+#![allow(clippy::single_char_add_str)]
+
+use std::fmt;
+use std::ops::Range;
+
+/**
+ * Evaluates escape sequences in the raw string.
+ *
+ * Returns `None` if the cooked value contains lone surrogates, which cannot be
+ * represented in a Rust string. Use [`cook_js_string`] to retain them.
+ */
+pub fn cook(raw: &str) -> Result<Option<String>, EscapeError> {
+    cook_js_string(raw, Grammar::Template).map(JsString::into_string)
+}
+
+/// The escape sequence grammar to cook with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Grammar {
+    /// Template literals, where legacy octal escapes are not allowed.
+    #[default]
+    Template,
+    /// String literals in strict mode code.
+    StrictString,
+    /// String literals in sloppy mode code, which may contain legacy octal escapes
+    /// such as `\101` and the identity escapes `\8` and `\9`.
+    SloppyString,
+}
+
+/**
+ * Evaluates escape sequences in the raw string, yielding the exact sequence of
+ * UTF-16 code units that the JavaScript runtime would produce.
+ *
+ * Escaped surrogates are paired up as in JavaScript: `\uD83D\uDE00` is the same as
+ * `\u{1F600}`.
+ */
+pub fn cook_js_string(raw: &str, grammar: Grammar) -> Result<JsString, EscapeError> {
+    cook_with(raw, grammar, None)
+}
+
+/**
+ * Evaluates escape sequences in the raw string, continuing past invalid ones.
+ *
+ * Returns the best-effort cooked value, where each invalid escape sequence is kept
+ * verbatim, along with all the errors found. Each escape sequence is judged on its
+ * own, as the tagged template grammar does with NotEscapeSequence.
+ */
+pub fn cook_best_effort(raw: &str, grammar: Grammar) -> (JsString, Vec<EscapeError>) {
+    let mut errors = Vec::new();
+    let cooked = cook_with(raw, grammar, Some(&mut errors)).unwrap();
+    (cooked, errors)
+}
+
+/// Cooks `raw`, stopping at the first error unless `errors` is given to collect them.
+fn cook_with(
+    raw: &str,
+    grammar: Grammar,
+    mut errors: Option<&mut Vec<EscapeError>>,
+) -> Result<JsString, EscapeError> {
+    let mut buf = JsStringBuf::with_capacity(raw.len());
+    let mut last = 0;
+    while last < raw.len() {
+        let Some(escape_pos) = raw[last..].find('\\').map(|x| last + x) else {
+            break;
+        };
+        buf.push_str(&raw[last..escape_pos]);
+        last = match cook_escape(raw, escape_pos, grammar, &mut buf) {
+            Ok(next) => next,
+            Err(e) => {
+                let Some(errors) = errors.as_mut() else {
+                    return Err(e);
+                };
+                buf.push_str(&raw[e.range.clone()]);
+                let next = e.range.end;
+                errors.push(e);
+                next
+            }
+        };
+    }
+    buf.push_str(&raw[last..]);
+    Ok(buf.finish())
+}
+
+/// Cooks the escape sequence at `escape_pos` into `buf`, returning the position after it.
+fn cook_escape(
+    raw: &str,
+    escape_pos: usize,
+    grammar: Grammar,
+    buf: &mut JsStringBuf,
+) -> Result<usize, EscapeError> {
+    let esc = &raw[escape_pos + 1..];
+    let esc0 = esc.as_bytes().first().copied().unwrap_or(b'\0');
+    match esc0 {
+        b'0' if !esc.as_bytes().get(1).map_or(false, u8::is_ascii_digit) => {
+            buf.push_str("\0");
+            Ok(escape_pos + 2)
+        }
+        b'0'..=b'7' if grammar == Grammar::SloppyString => {
+            // Up to 3 digits, as long as the value fits in a byte
+            let max_len = if esc0 <= b'3' { 3 } else { 2 };
+            let len = esc
+                .bytes()
+                .take(max_len)
+                .take_while(|ch| (b'0'..=b'7').contains(ch))
+                .count();
+            let ord = u8::from_str_radix(&esc[..len], 8).unwrap();
+            buf.push(ord as char);
+            Ok(escape_pos + 1 + len)
+        }
+        b'0'..=b'7' => {
+            let len = if esc0 == b'0' { 3 } else { 2 };
+            let kind = if grammar == Grammar::Template {
+                EscapeErrorKind::LegacyOctalEscape
+            } else {
+                EscapeErrorKind::StrictLegacyOctalEscape
+            };
+            Err(EscapeError::new(kind, escape_pos..escape_pos + len))
+        }
+        b'8'..=b'9' if grammar == Grammar::SloppyString => {
+            buf.push(esc0 as char);
+            Ok(escape_pos + 2)
+        }
+        b'8'..=b'9' => {
+            let kind = if grammar == Grammar::Template {
+                EscapeErrorKind::NonOctalEscape
+            } else {
+                EscapeErrorKind::StrictNonOctalEscape
+            };
+            Err(EscapeError::new(kind, escape_pos..escape_pos + 2))
+        }
+        b'x' => {
+            let digits = count_hex_digits(&esc[1..], 2);
+            if digits == 2 {
+                let ord = u8::from_str_radix(&esc[1..3], 16).unwrap();
+                buf.push(ord as char);
+                Ok(escape_pos + 4)
+            } else {
+                Err(EscapeError::new(
+                    EscapeErrorKind::InvalidHexEscape,
+                    escape_pos..escape_pos + 2 + digits,
+                ))
+            }
+        }
+        b'u' => {
+            let digits = count_hex_digits(&esc[1..], 4);
+            let (ord, esc_len) = if digits == 4 {
+                let ord = u32::from_str_radix(&esc[1..5], 16).unwrap();
+                (ord, 6)
+            } else if esc.as_bytes().get(1) == Some(&b'{') {
+                let mut i = 2;
+                while i < esc.len() && esc.as_bytes()[i].is_ascii_hexdigit() {
+                    i += 1;
+                }
+                if i < esc.len() && esc.as_bytes()[i] == b'}' {
+                    i += 1;
+                } else {
+                    return Err(EscapeError::new(
+                        EscapeErrorKind::InvalidUnicodeEscape,
+                        escape_pos..escape_pos + 1 + i,
+                    ));
+                }
+                let range = escape_pos..escape_pos + 1 + i;
+                let ord = u32::from_str_radix(&esc[2..i - 1], 16).map_err(|_| {
+                    EscapeError::new(EscapeErrorKind::UndefinedCodePoint, range.clone())
+                })?;
+                if ord >= 0x110000 {
+                    return Err(EscapeError::new(EscapeErrorKind::UndefinedCodePoint, range));
+                }
+                (ord, i + 1)
+            } else {
+                return Err(EscapeError::new(
+                    EscapeErrorKind::InvalidUnicodeEscape,
+                    escape_pos..escape_pos + 2 + digits,
+                ));
+            };
+            if let Ok(unit) = u16::try_from(ord) {
+                buf.push_code_unit(unit);
+            } else {
+                buf.push(char::from_u32(ord).unwrap());
+            }
+            Ok(escape_pos + esc_len)
+        }
+        b'b' => {
+            buf.push_str("\x08");
+            Ok(escape_pos + 2)
+        }
+        b'f' => {
+            buf.push_str("\x0C");
+            Ok(escape_pos + 2)
+        }
+        b'n' => {
+            buf.push_str("\n");
+            Ok(escape_pos + 2)
+        }
+        b'r' => {
+            buf.push_str("\r");
+            Ok(escape_pos + 2)
+        }
+        b't' => {
+            buf.push_str("\t");
+            Ok(escape_pos + 2)
+        }
+        b'v' => {
+            buf.push_str("\x0B");
+            Ok(escape_pos + 2)
+        }
+        b'\\' => {
+            buf.push_str("\\");
+            Ok(escape_pos + 2)
+        }
+        _ => {
+            // Identity escapes and line continuations
+            let newline_len = ["\r\n", "\n", "\r", "\u{2028}", "\u{2029}"]
+                .iter()
+                .find_map(|&needle| esc.starts_with(needle).then_some(needle.len()));
+            Ok(escape_pos + 1 + newline_len.unwrap_or(0))
+        }
+    }
+}
+
+/**
+ * A cooked string value, which may contain lone surrogates unlike Rust strings.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsString {
+    /// The value is well-formed UTF-16 and is represented as a Rust string.
+    Utf8(String),
+    /// The value contains lone surrogates and is represented as UTF-16 code units.
+    Utf16(Vec<u16>),
+}
+
+impl JsString {
+    /// Returns the string if it is free of lone surrogates.
+    pub fn into_string(self) -> Option<String> {
+        match self {
+            JsString::Utf8(s) => Some(s),
+            JsString::Utf16(_) => None,
+        }
+    }
+}
+
+/// Accumulates a [`JsString`], switching to UTF-16 once a lone surrogate appears.
+struct JsStringBuf {
+    buf: String,
+    units: Option<Vec<u16>>,
+    /// A high surrogate that may be paired with the next code unit
+    pending_high: Option<u16>,
+}
+
+impl JsStringBuf {
+    fn with_capacity(capacity: usize) -> Self {
+        JsStringBuf {
+            buf: String::with_capacity(capacity),
+            units: None,
+            pending_high: None,
+        }
+    }
+
+    fn push_str(&mut self, s: &str) {
+        if s.is_empty() {
+            return;
+        }
+        self.flush_high();
+        if let Some(units) = &mut self.units {
+            units.extend(s.encode_utf16());
+        } else {
+            self.buf.push_str(s);
+        }
+    }
+
+    fn push(&mut self, ch: char) {
+        self.push_str(ch.encode_utf8(&mut [0; 4]));
+    }
+
+    fn push_code_unit(&mut self, unit: u16) {
+        match unit {
+            0xD800..=0xDBFF => {
+                self.flush_high();
+                self.pending_high = Some(unit);
+            }
+            0xDC00..=0xDFFF => {
+                if let Some(high) = self.pending_high.take() {
+                    let ord =
+                        0x10000 + ((u32::from(high) - 0xD800) << 10) + (u32::from(unit) - 0xDC00);
+                    self.push(char::from_u32(ord).unwrap());
+                } else {
+                    self.push_lone_surrogate(unit);
+                }
+            }
+            _ => self.push(char::from_u32(u32::from(unit)).unwrap()),
+        }
+    }
+
+    fn flush_high(&mut self) {
+        if let Some(high) = self.pending_high.take() {
+            self.push_lone_surrogate(high);
+        }
+    }
+
+    fn push_lone_surrogate(&mut self, unit: u16) {
+        self.units
+            .get_or_insert_with(|| self.buf.encode_utf16().collect())
+            .push(unit);
+    }
+
+    fn finish(mut self) -> JsString {
+        self.flush_high();
+        match self.units {
+            Some(units) => JsString::Utf16(units),
+            None => JsString::Utf8(self.buf),
+        }
+    }
+}
+
+/// Counts the leading hexadecimal digits of `s`, up to `max`.
+fn count_hex_digits(s: &str, max: usize) -> usize {
+    s.bytes()
+        .take(max)
+        .take_while(|ch| ch.is_ascii_hexdigit())
+        .count()
+}
+
+/**
+ * An invalid escape sequence found by [`cook`].
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscapeError {
+    kind: EscapeErrorKind,
+    range: Range<usize>,
+}
+
+impl EscapeError {
+    fn new(kind: EscapeErrorKind, range: Range<usize>) -> Self {
+        EscapeError { kind, range }
+    }
+
+    /// The kind of the error.
+    pub fn kind(&self) -> EscapeErrorKind {
+        self.kind
+    }
+
+    /// Byte range of the offending escape sequence within the raw string,
+    /// starting at the backslash.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+}
+
+impl fmt::Display for EscapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+impl std::error::Error for EscapeError {}
+
+/// The kind of an [`EscapeError`], whose `Display` gives the message the runtime throws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EscapeErrorKind {
+    /// `\1` to `\7`, or `\0` followed by a digit, in a template
+    LegacyOctalEscape,
+    /// `\8` or `\9` in a template
+    NonOctalEscape,
+    /// `\1` to `\7`, or `\0` followed by a digit, in a strict mode string literal
+    StrictLegacyOctalEscape,
+    /// `\8` or `\9` in a strict mode string literal
+    StrictNonOctalEscape,
+    /// `\x` not followed by two hexadecimal digits
+    InvalidHexEscape,
+    /// `\u{...}` beyond U+10FFFF
+    UndefinedCodePoint,
+    /// `\u` not followed by four hexadecimal digits or a braced code point
+    InvalidUnicodeEscape,
+}
+
+impl fmt::Display for EscapeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EscapeErrorKind::LegacyOctalEscape => write!(
+                f,
+                "Octal escape sequences are not allowed in template strings."
+            ),
+            EscapeErrorKind::NonOctalEscape => {
+                write!(f, "\\8 and \\9 are not allowed in template strings.")
+            }
+            EscapeErrorKind::StrictLegacyOctalEscape => {
+                write!(f, "Octal escape sequences are not allowed in strict mode.")
+            }
+            EscapeErrorKind::StrictNonOctalEscape => {
+                write!(f, "\\8 and \\9 are not allowed in strict mode.")
+            }
+            EscapeErrorKind::InvalidHexEscape => write!(f, "Invalid hexadecimal escape sequence"),
+            EscapeErrorKind::UndefinedCodePoint => write!(f, "Undefined Unicode code-point"),
+            EscapeErrorKind::InvalidUnicodeEscape => write!(f, "Invalid Unicode escape sequence"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keep_plain_text() {
+        assert_eq!(cook("foo").unwrap().unwrap(), "foo");
+    }
+
+    #[test]
+    fn test_expand_simple_escapes() {
+        assert_eq!(
+            cook("\\` \\$ \\{ \\' \\\" \\\\ \\b \\f \\n \\r \\t \\v \\0")
+                .unwrap()
+                .unwrap(),
+            "` $ { ' \" \\ \x08 \x0C \n \r \t \x0B \0"
+        );
+    }
+
+    #[test]
+    fn test_expand_identity_escapes() {
+        assert_eq!(cook("\\a \\% \\( \\)").unwrap().unwrap(), "a % ( )");
+    }
+
+    #[test]
+    fn test_teject_non_octal_8() {
+        assert_eq!(
+            cook("\\8").unwrap_err().to_string(),
+            "\\8 and \\9 are not allowed in template strings."
+        );
+    }
+
+    #[test]
+    fn test_reject_non_octal_9() {
+        assert_eq!(
+            cook("\\9").unwrap_err().to_string(),
+            "\\8 and \\9 are not allowed in template strings."
+        );
+    }
+
+    #[test]
+    fn test_reject_octal_single_digit() {
+        assert_eq!(
+            cook("\\7").unwrap_err().to_string(),
+            "Octal escape sequences are not allowed in template strings."
+        );
+    }
+
+    #[test]
+    fn test_reject_octal_leading_zero() {
+        assert_eq!(
+            cook("\\01").unwrap_err().to_string(),
+            "Octal escape sequences are not allowed in template strings."
+        );
+    }
+
+    #[test]
+    fn test_expand_hex_escapes() {
+        assert_eq!(
+            cook("\\x00 \\x61 \\xA0").unwrap().unwrap(),
+            "\x00 \x61 \u{A0}"
+        );
+    }
+
+    #[test]
+    fn test_reject_incomplete_hex_single_digit_non_eof() {
+        assert_eq!(
+            cook("\\x8%").unwrap_err().to_string(),
+            "Invalid hexadecimal escape sequence"
+        );
+    }
+
+    #[test]
+    fn test_reject_incomplete_hex_single_digit_eof() {
+        assert_eq!(
+            cook("\\x8").unwrap_err().to_string(),
+            "Invalid hexadecimal escape sequence"
+        );
+    }
+
+    #[test]
+    fn test_reject_incomplete_hex_no_digit_non_eof() {
+        assert_eq!(
+            cook("\\x%%").unwrap_err().to_string(),
+            "Invalid hexadecimal escape sequence"
+        );
+    }
+
+    #[test]
+    fn test_reject_incomplete_hex_no_digit_eof() {
+        assert_eq!(
+            cook("\\x").unwrap_err().to_string(),
+            "Invalid hexadecimal escape sequence"
+        );
+    }
+
+    #[test]
+    fn test_expand_simple_unicode_escapes() {
+        assert_eq!(
+            cook("\\u0000 \\u3030").unwrap().unwrap(),
+            "\u{0000} \u{3030}"
+        );
+    }
+
+    #[test]
+    fn test_expand_braced_unicode_escapes() {
+        assert_eq!(cook("\\u{12345}").unwrap().unwrap(), "\u{12345}");
+    }
+
+    #[test]
+    fn test_expand_surrogate_codepoint_escapes() {
+        assert_eq!(cook("\\uDCBA \\uDEF0 \\u{DCBA} \\u{DEF0}").unwrap(), None);
+        assert_eq!(
+            cook_js_string("\\uDCBA \\uDEF0 \\u{DCBA} \\u{DEF0}", Grammar::Template).unwrap(),
+            JsString::Utf16(vec![0xDCBA, 0x20, 0xDEF0, 0x20, 0xDCBA, 0x20, 0xDEF0])
+        );
+    }
+
+    #[test]
+    fn test_join_surrogate_pairs() {
+        assert_eq!(cook("\\uD83D\\uDE00").unwrap().unwrap(), "\u{1F600}");
+        assert_eq!(cook("\\u{D83D}\\u{DE00}").unwrap().unwrap(), "\u{1F600}");
+        assert_eq!(cook("\\uD83D\\u{DE00}").unwrap().unwrap(), "\u{1F600}");
+        // Line continuations produce no code units in between
+        assert_eq!(cook("\\uD83D\\\n\\uDE00").unwrap().unwrap(), "\u{1F600}");
+    }
+
+    #[test]
+    fn test_keep_lone_surrogates() {
+        assert_eq!(
+            cook_js_string("a\\uD83Db", Grammar::Template).unwrap(),
+            JsString::Utf16(vec![0x61, 0xD83D, 0x62])
+        );
+        assert_eq!(
+            cook_js_string("\\uDE00\\uD83D", Grammar::Template).unwrap(),
+            JsString::Utf16(vec![0xDE00, 0xD83D])
+        );
+        assert_eq!(
+            cook_js_string("\\uD83D\\uD83D\\uDE00", Grammar::Template).unwrap(),
+            JsString::Utf16(vec![0xD83D, 0xD83D, 0xDE00])
+        );
+        assert_eq!(
+            cook_js_string("\u{1F600}\\uD83D", Grammar::Template).unwrap(),
+            JsString::Utf16(vec![0xD83D, 0xDE00, 0xD83D])
+        );
+    }
+
+    #[test]
+    fn test_expand_short_unicode_escapes() {
+        assert_eq!(cook("\\u{A}").unwrap().unwrap(), "\u{A}");
+    }
+
+    #[test]
+    fn test_expands_long_unicode_escapes() {
+        assert_eq!(cook("\\u{000000000012345}").unwrap().unwrap(), "\u{12345}");
+    }
+
+    #[test]
+    fn test_reject_incomplete_unicode_escapes_2_digits_out_of_4() {
+        assert_eq!(
+            cook("\\uA0$").unwrap_err().to_string(),
+            "Invalid Unicode escape sequence"
+        );
+    }
+
+    #[test]
+    fn test_reject_incomplete_unicode_escapes_missing_closing_braces() {
+        assert_eq!(
+            cook("\\u{A").unwrap_err().to_string(),
+            "Invalid Unicode escape sequence"
+        );
+    }
+
+    #[test]
+    fn test_reject_incomplete_unicode_escapes_invalid_character_in_the_braces() {
+        assert_eq!(
+            cook("\\u{A B}").unwrap_err().to_string(),
+            "Invalid Unicode escape sequence"
+        );
+    }
+
+    #[test]
+    fn test_reject_large_unicode_escapes() {
+        assert_eq!(
+            cook("\\u{ABCDEF}").unwrap_err().to_string(),
+            "Undefined Unicode code-point"
+        );
+    }
+
+    #[test]
+    fn test_remove_line_continuation() {
+        assert_eq!(
+            cook("[\\\n] [\\\u{2028}] [\\\u{2029}]").unwrap().unwrap(),
+            "[] [] []"
+        );
+    }
+
+    #[test]
+    fn test_report_escape_ranges() {
+        let range = |raw: &str| cook(raw).unwrap_err().range;
+        assert_eq!(range("foo \\01"), 4..7);
+        assert_eq!(range("foo \\7"), 4..6);
+        assert_eq!(range("foo \\9 bar"), 4..6);
+        assert_eq!(range("foo \\x8%"), 4..7);
+        assert_eq!(range("foo \\x"), 4..6);
+        assert_eq!(range("foo \\uA0$"), 4..8);
+        assert_eq!(range("foo \\u{A B}"), 4..8);
+        assert_eq!(range("foo \\u{A"), 4..8);
+        assert_eq!(range("foo \\u{ABCDEF} bar"), 4..14);
+        assert_eq!(range("\\n \\u{ABCDEF}"), 3..13);
+    }
+
+    #[test]
+    fn test_collect_all_errors() {
+        let (cooked, errors) = cook_best_effort("a\\9b\\nc\\x4\\u{110000}\\x41", Grammar::Template);
+        assert_eq!(
+            cooked,
+            JsString::Utf8("a\\9b\nc\\x4\\u{110000}A".to_owned())
+        );
+        assert_eq!(
+            errors,
+            [
+                EscapeError::new(EscapeErrorKind::NonOctalEscape, 1..3),
+                EscapeError::new(EscapeErrorKind::InvalidHexEscape, 7..10),
+                EscapeError::new(EscapeErrorKind::UndefinedCodePoint, 10..20),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_no_errors() {
+        assert_eq!(
+            cook_best_effort("foo\\n", Grammar::Template),
+            (JsString::Utf8("foo\n".to_owned()), vec![])
+        );
+    }
+
+    fn cook_string(raw: &str, grammar: Grammar) -> Result<String, EscapeError> {
+        cook_js_string(raw, grammar).map(|s| s.into_string().unwrap())
+    }
+
+    #[test]
+    fn test_strict_string_escapes() {
+        assert_eq!(
+            cook_string("\\' \\\" \\0 \\x41 \\u{42} \\\r\n", Grammar::StrictString).unwrap(),
+            "' \" \0 A B "
+        );
+        assert_eq!(
+            cook_string("\\01", Grammar::StrictString)
+                .unwrap_err()
+                .to_string(),
+            "Octal escape sequences are not allowed in strict mode."
+        );
+        assert_eq!(
+            cook_string("\\7", Grammar::StrictString).unwrap_err().kind,
+            EscapeErrorKind::StrictLegacyOctalEscape
+        );
+        assert_eq!(
+            cook_string("\\8", Grammar::StrictString)
+                .unwrap_err()
+                .to_string(),
+            "\\8 and \\9 are not allowed in strict mode."
+        );
+    }
+
+    #[test]
+    fn test_sloppy_string_legacy_octal_escapes() {
+        let cook_sloppy = |raw| cook_string(raw, Grammar::SloppyString).unwrap();
+        assert_eq!(cook_sloppy("\\0"), "\0");
+        assert_eq!(cook_sloppy("\\101\\7"), "A\x07");
+        assert_eq!(cook_sloppy("\\377\\400"), "\u{FF}\x200");
+        assert_eq!(cook_sloppy("\\08"), "\08");
+        assert_eq!(cook_sloppy("\\1234"), "S4");
+        assert_eq!(cook_sloppy("\\477"), "\x277");
+        assert_eq!(cook_sloppy("\\8\\9"), "89");
+    }
+
+    #[test]
+    fn test_sloppy_string_other_errors() {
+        assert_eq!(
+            cook_string("\\x4", Grammar::SloppyString).unwrap_err().kind,
+            EscapeErrorKind::InvalidHexEscape
+        );
+        assert_eq!(
+            cook_string("\\u{110000}", Grammar::SloppyString)
+                .unwrap_err()
+                .kind,
+            EscapeErrorKind::UndefinedCodePoint
+        );
+    }
+}
@@ -0,0 +1,845 @@
+use std::borrow::Cow;
+use std::iter;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+
+/**
+ * Removes the common indentation from the raw texts of a template, as the runtime
+ * `dedent` does.
+ *
+ * `raw` is the list of the raw texts between the substitutions. Indentation is measured
+ * on the lines following a line terminator; a line consisting only of indentation
+ * counts unless it is followed by a substitution.
+ *
+ * Elements that need no change are borrowed from `raw` rather than copied.
+ */
+// NOTE: as the raw texts come from the source string, which in turn is encoded in UTF-8,
+// they are known to be free of stray surrogates.
+pub fn dedent_raw<S>(raw: &[S]) -> Vec<Cow<'_, str>>
+where
+    S: AsRef<str>,
+{
+    dedent_raw_with(raw, &DedentOptions::default())
+}
+
+/**
+ * Options for [`dedent_raw_with`].
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DedentOptions {
+    /// Keeps CR and CRLF line terminators as they are, instead of converting them
+    /// to LF as the template raw value (TRV) does.
+    pub preserve_line_terminators: bool,
+    /// Measures indentation in columns, advancing tabs to the next multiple of this width.
+    /// If unset, each indentation character counts as one.
+    pub tab_width: Option<NonZeroUsize>,
+    /// Characters that make up indentation.
+    pub whitespace: Whitespace,
+}
+
+/// A set of characters treated as indentation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Whitespace {
+    /// ASCII space and tab, as the runtime does
+    #[default]
+    Ascii,
+    /// ECMAScript WhiteSpace: tab, VT, FF, ZWNBSP (U+FEFF) and the Zs category,
+    /// which includes NBSP and U+3000 IDEOGRAPHIC SPACE
+    EcmaScript,
+    /// The given characters; line terminators are ignored
+    Custom(Vec<char>),
+}
+
+impl Whitespace {
+    /// Is `ch` part of indentation?
+    pub fn contains(&self, ch: char) -> bool {
+        match self {
+            Whitespace::Ascii => matches!(ch, ' ' | '\t'),
+            Whitespace::EcmaScript => {
+                matches!(ch, '\t' | '\x0B' | '\x0C' | '\u{FEFF}') || is_space_separator(ch)
+            }
+            Whitespace::Custom(chars) => {
+                !matches!(ch, '\n' | '\r' | '\u{2028}' | '\u{2029}') && chars.contains(&ch)
+            }
+        }
+    }
+}
+
+/// Is `ch` in the Unicode category Zs?
+fn is_space_separator(ch: char) -> bool {
+    matches!(
+        ch,
+        ' ' | '\u{A0}' | '\u{1680}' | '\u{202F}' | '\u{205F}' | '\u{3000}'
+    ) || ('\u{2000}'..='\u{200A}').contains(&ch)
+}
+
+/**
+ * Same as [`dedent_raw`], with options.
+ *
+ * CR and CRLF, which may remain in the raw texts that did not come through
+ * a JavaScript parser, are recognized as line terminators.
+ */
+pub fn dedent_raw_with<'a, S>(raw: &'a [S], options: &DedentOptions) -> Vec<Cow<'a, str>>
+where
+    S: AsRef<str>,
+{
+    let level = indent_level(raw, options);
+    raw.iter()
+        .map(|elem| dedent_elem(elem.as_ref(), level, options, None))
+        .collect::<Vec<_>>()
+}
+
+/**
+ * Same as [`dedent_raw_with`], but also returns an [`OffsetMap`] for each element
+ * to relate the positions in the dedented texts to those in the original ones.
+ */
+pub fn dedent_raw_mapped<'a, S>(
+    raw: &'a [S],
+    options: &DedentOptions,
+) -> (Vec<Cow<'a, str>>, Vec<OffsetMap>)
+where
+    S: AsRef<str>,
+{
+    let level = indent_level(raw, options);
+    raw.iter()
+        .map(|elem| {
+            let mut map = OffsetMap::default();
+            let dedented = dedent_elem(elem.as_ref(), level, options, Some(&mut map.edits));
+            (dedented, map)
+        })
+        .unzip()
+}
+
+fn dedent_elem<'a>(
+    elem: &'a str,
+    level: usize,
+    options: &DedentOptions,
+    mut edits: Option<&mut Vec<Edit>>,
+) -> Cow<'a, str> {
+    if !needs_dedent(elem, level, options) {
+        return Cow::Borrowed(elem);
+    }
+    let mut buf = String::with_capacity(elem.len());
+    let mut last = 0;
+    let mut record = |original: Range<usize>, dedented: Range<usize>| {
+        if let Some(edits) = &mut edits {
+            if !(original.is_empty() && dedented.is_empty()) {
+                edits.push(Edit { original, dedented });
+            }
+        }
+    };
+    while let Some(newline) = find_newline(elem, last) {
+        if options.preserve_line_terminators {
+            buf.push_str(&elem[last..newline.end]);
+        } else {
+            buf.push_str(&elem[last..newline.start]);
+            let start = buf.len();
+            buf.push_str(normalize_newline(&elem[newline.clone()]));
+            if elem.as_bytes()[newline.start] == b'\r' {
+                record(newline.clone(), start..buf.len());
+            }
+        }
+        let pos = newline.end;
+        let end_pos = find_non_space(elem, pos, &options.whitespace);
+        let (removed, fill) = split_indent(&elem[pos..end_pos], level, options.tab_width);
+        let start = buf.len();
+        buf.extend(iter::repeat(' ').take(fill));
+        record(pos..pos + removed, start..buf.len());
+        buf.push_str(&elem[pos + removed..end_pos]);
+
+        last = end_pos;
+    }
+    buf.push_str(&elem[last..]);
+    Cow::Owned(buf)
+}
+
+/**
+ * Relates byte offsets in an element returned by [`dedent_raw_mapped`]
+ * to those in the element it was computed from.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OffsetMap {
+    edits: Vec<Edit>,
+}
+
+/**
+ * A range of the original text replaced in the dedented text: removed indentation,
+ * possibly with spaces filling in for a straddling tab, or a normalized line terminator.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    /// Byte range in the original text
+    pub original: Range<usize>,
+    /// Byte range of the replacement in the dedented text, empty if removed
+    pub dedented: Range<usize>,
+}
+
+impl OffsetMap {
+    /// The replaced ranges, in order.
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// Translates an offset in the dedented text into the original text.
+    ///
+    /// An offset within a replacement maps to the corresponding position
+    /// counted from the end of the replaced range.
+    pub fn to_original(&self, offset: usize) -> usize {
+        let (mut pos, mut orig_pos) = (0, 0);
+        for edit in &self.edits {
+            if edit.dedented.start > offset {
+                break;
+            }
+            if offset < edit.dedented.end {
+                return edit
+                    .original
+                    .end
+                    .saturating_sub(edit.dedented.end - offset)
+                    .max(edit.original.start);
+            }
+            pos = edit.dedented.end;
+            orig_pos = edit.original.end;
+        }
+        orig_pos + (offset - pos)
+    }
+
+    /// Translates the end of a range in the dedented text into the original text.
+    ///
+    /// Unlike [`to_original`](Self::to_original), this maps to the end of the
+    /// preceding byte, so that a range ending at a replacement does not extend into it.
+    pub fn to_original_end(&self, offset: usize) -> usize {
+        match offset {
+            0 => 0,
+            _ => self.to_original(offset - 1) + 1,
+        }
+    }
+
+    /// Translates an offset in the original text into the dedented text.
+    ///
+    /// An offset within a replaced range maps to the corresponding position
+    /// counted from the end of the replacement.
+    pub fn to_dedented(&self, offset: usize) -> usize {
+        let (mut pos, mut orig_pos) = (0, 0);
+        for edit in &self.edits {
+            if edit.original.start > offset {
+                break;
+            }
+            if offset < edit.original.end {
+                return edit
+                    .dedented
+                    .end
+                    .saturating_sub(edit.original.end - offset)
+                    .max(edit.dedented.start);
+            }
+            pos = edit.dedented.end;
+            orig_pos = edit.original.end;
+        }
+        pos + (offset - orig_pos)
+    }
+}
+
+/// Does [`dedent_raw_with`] change `elem`?
+fn needs_dedent(elem: &str, level: usize, options: &DedentOptions) -> bool {
+    if level == 0 && (options.preserve_line_terminators || !elem.contains('\r')) {
+        return false;
+    }
+    let mut last = 0;
+    while let Some(newline) = find_newline(elem, last) {
+        if !options.preserve_line_terminators && elem.as_bytes()[newline.start] == b'\r' {
+            return true;
+        }
+        let pos = newline.end;
+        let end_pos = find_non_space(elem, pos, &options.whitespace);
+        if split_indent(&elem[pos..end_pos], level, options.tab_width) != (0, 0) {
+            return true;
+        }
+        last = end_pos;
+    }
+    false
+}
+
+/// Computes the common indentation width to remove.
+fn indent_level<S>(raw: &[S], options: &DedentOptions) -> usize
+where
+    S: AsRef<str>,
+{
+    let mut level = usize::MAX / 2;
+    for (i, elem) in raw.iter().enumerate() {
+        let elem: &str = elem.as_ref();
+        let mut last = 0;
+        while let Some(newline) = find_newline(elem, last) {
+            let pos = newline.end;
+            let end_pos = find_non_space(elem, pos, &options.whitespace);
+            let len = indent_width(&elem[pos..end_pos], options.tab_width);
+            let has_content = if end_pos < elem.len() {
+                // There is a character in the line
+                !has_newline_at(elem, end_pos)
+            } else {
+                // There is a substitution
+                i + 1 < raw.len()
+            };
+            if has_content {
+                level = level.min(len);
+            }
+            last = end_pos;
+        }
+    }
+    level
+}
+
+/**
+ * A line whose removed indentation differs from that of the other lines,
+ * e.g. a line indented with a tab among lines indented with spaces.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InconsistentIndent {
+    /// Index of the element containing the line
+    pub index: usize,
+    /// Byte range of the line within the element, excluding the line terminator
+    pub line: Range<usize>,
+}
+
+/**
+ * Finds the lines whose indentation removed by [`dedent_raw_with`] is not
+ * byte-identical to the one removed from the first line.
+ *
+ * Lines shorter than the common indentation are reported only if what they have
+ * does not agree with the first line.
+ */
+pub fn find_inconsistent_indents<S>(raw: &[S], options: &DedentOptions) -> Vec<InconsistentIndent>
+where
+    S: AsRef<str>,
+{
+    let level = indent_level(raw, options);
+    let removed_prefixes = raw.iter().enumerate().flat_map(|(index, elem)| {
+        let elem: &str = elem.as_ref();
+        let mut lines = Vec::new();
+        let mut last = 0;
+        while let Some(newline) = find_newline(elem, last) {
+            let pos = newline.end;
+            let end_pos = find_non_space(elem, pos, &options.whitespace);
+            let (removed, _) = split_indent(&elem[pos..end_pos], level, options.tab_width);
+            let line_end = find_newline(elem, end_pos).map_or(elem.len(), |nl| nl.start);
+            lines.push((index, pos..line_end, &elem[pos..pos + removed]));
+            last = end_pos;
+        }
+        lines
+    });
+    let removed_prefixes = removed_prefixes.collect::<Vec<_>>();
+    let Some(reference) = removed_prefixes
+        .iter()
+        .map(|&(_, _, prefix)| prefix)
+        .find(|prefix| indent_width(prefix, options.tab_width) >= level)
+    else {
+        return Vec::new();
+    };
+    removed_prefixes
+        .into_iter()
+        .filter(|&(_, _, prefix)| !reference.starts_with(prefix))
+        .map(|(index, line, _)| InconsistentIndent { index, line })
+        .collect()
+}
+
+/// Measures the width of `indent`, which consists of whitespace characters.
+fn indent_width(indent: &str, tab_width: Option<NonZeroUsize>) -> usize {
+    match tab_width {
+        None => indent.chars().count(),
+        Some(tab_width) => indent
+            .chars()
+            .fold(0, |col, ch| next_column(col, ch, tab_width)),
+    }
+}
+
+/// Returns the number of bytes of `indent` to remove so that `level` columns are removed,
+/// and the number of spaces to fill in for a straddling tab.
+fn split_indent(indent: &str, level: usize, tab_width: Option<NonZeroUsize>) -> (usize, usize) {
+    let mut col = 0;
+    for (i, ch) in indent.char_indices() {
+        if col >= level {
+            return (i, 0);
+        }
+        let Some(tab_width) = tab_width else {
+            col += 1;
+            continue;
+        };
+        let next = next_column(col, ch, tab_width);
+        if next > level {
+            return (i + ch.len_utf8(), next - level);
+        }
+        col = next;
+    }
+    (indent.len(), 0)
+}
+
+fn next_column(col: usize, ch: char, tab_width: NonZeroUsize) -> usize {
+    if ch == '\t' {
+        (col / tab_width + 1) * tab_width.get()
+    } else {
+        col + 1
+    }
+}
+
+/// Returns the indentation that aligns a continuation line with the end of `elem`,
+/// i.e. with the column where a substitution following `elem` appears.
+///
+/// The leading indentation of the last line is kept as is, and the rest of the line
+/// is filled with spaces, measured in columns as [`dedent_raw_with`] does.
+/// `at_line_start` tells whether `elem` itself starts at the beginning of a line;
+/// if not and `elem` contains no line terminator, the column is unknown.
+///
+/// Prefixing the continuation lines of a multi-line value with this indentation keeps
+/// them aligned when the value is substituted after `elem`.
+pub fn substitution_indent(
+    elem: &str,
+    at_line_start: bool,
+    options: &DedentOptions,
+) -> Option<String> {
+    let mut line_start = at_line_start.then_some(0);
+    let mut last = 0;
+    while let Some(newline) = find_newline(elem, last) {
+        line_start = Some(newline.end);
+        last = newline.end;
+    }
+    let line = &elem[line_start?..];
+    let indent = &line[..find_non_space(line, 0, &options.whitespace)];
+    let fill = indent_width(line, options.tab_width) - indent_width(indent, options.tab_width);
+    let mut buf = String::with_capacity(indent.len() + fill);
+    buf.push_str(indent);
+    buf.extend(iter::repeat(' ').take(fill));
+    Some(buf)
+}
+
+/// Returns the range of the first line terminator at or after `from`.
+fn find_newline(s: &str, from: usize) -> Option<Range<usize>> {
+    let s: &[u8] = s.as_ref();
+    let mut i = from;
+    // Only LF, CR and the lead byte of U+2028 and U+2029 need a closer look
+    while let Some(offset) = s[i..]
+        .iter()
+        .position(|&ch| matches!(ch, b'\n' | b'\r' | 0xE2))
+    {
+        let pos = i + offset;
+        if let Some(len) = check_newline_at(s, pos) {
+            return Some(pos..pos + len);
+        }
+        i = pos + 1;
+    }
+    None
+}
+
+fn has_newline_at(s: &str, i: usize) -> bool {
+    check_newline_at(s, i).is_some()
+}
+
+fn check_newline_at<S>(s: &S, i: usize) -> Option<usize>
+where
+    S: AsRef<[u8]> + ?Sized,
+{
+    let s: &[u8] = s.as_ref();
+    match s[i..] {
+        [b'\r', b'\n', ..] => Some(2),
+        [b'\n' | b'\r', ..] => Some(1),
+        // U+2028 and U+2029
+        [0xE2, 0x80, 0xA8 | 0xA9, ..] => Some(3),
+        _ => None,
+    }
+}
+
+/// Converts CR and CRLF into LF.
+fn normalize_newline(newline: &str) -> &str {
+    match newline {
+        "\r" | "\r\n" => "\n",
+        _ => newline,
+    }
+}
+
+fn find_non_space(s: &str, from: usize, whitespace: &Whitespace) -> usize {
+    if *whitespace == Whitespace::Ascii {
+        let s: &[u8] = s.as_ref();
+        let mut i = from;
+        while i < s.len() && (s[i] == b' ' || s[i] == b'\t') {
+            i += 1;
+        }
+        return i;
+    }
+    s[from..]
+        .char_indices()
+        .find(|&(_, ch)| !whitespace.contains(ch))
+        .map_or(s.len(), |(i, _)| from + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_do_not_remove_initial_spaces() {
+        assert_eq!(dedent_raw(&["    foo bar "]), ["    foo bar "]);
+    }
+    #[test]
+    fn test_do_not_remove_remove_initial_tabs() {
+        assert_eq!(dedent_raw(&["\tfoo\tbar\t"]), ["\tfoo\tbar\t"]);
+    }
+    #[test]
+    fn test_keep_empty_line_first_line_case() {
+        assert_eq!(dedent_raw(&["\nfoo bar "]), ["\nfoo bar "]);
+    }
+    #[test]
+    fn test_keep_the_last_newlines() {
+        assert_eq!(dedent_raw(&["x\n\n\n"]), ["x\n\n\n"]);
+    }
+    #[test]
+    fn test_keep_spaces_after_substitution() {
+        assert_eq!(dedent_raw(&["foo", " bar"]), ["foo", " bar"]);
+    }
+    #[test]
+    fn test_keep_spaces_after_bar() {
+        assert_eq!(dedent_raw(&["foo", " bar"]), ["foo", " bar"]);
+    }
+    #[test]
+    fn test_dedent_the_second_line_without_substitution() {
+        assert_eq!(dedent_raw(&["foo\n  bar "]), ["foo\nbar "]);
+    }
+    #[test]
+    fn test_dedent_the_second_line_with_substitution() {
+        assert_eq!(dedent_raw(&["foo", "foo\n  bar "]), ["foo", "foo\nbar "]);
+    }
+    #[test]
+    fn test_dedent_the_second_line_tab_case_without_substitution() {
+        assert_eq!(dedent_raw(&["foo\n\t\tbar\t"]), ["foo\nbar\t"]);
+    }
+    #[test]
+    fn test_dedent_the_second_line_tab_case_with_substitution() {
+        assert_eq!(
+            dedent_raw(&["foo", "foo\n\t\tbar\t"]),
+            ["foo", "foo\nbar\t"]
+        );
+    }
+    #[test]
+    fn test_dedent_with_variable_indentation_1() {
+        assert_eq!(dedent_raw(&["foo\nbar"]), ["foo\nbar"]);
+    }
+    #[test]
+    fn test_dedent_with_variable_indentation_2() {
+        assert_eq!(dedent_raw(&["foo\n bar"]), ["foo\nbar"]);
+    }
+    #[test]
+    fn test_dedent_with_variable_indentation_3() {
+        assert_eq!(dedent_raw(&["foo\n  bar"]), ["foo\nbar"]);
+    }
+    #[test]
+    fn test_dedent_with_variable_indentation_4() {
+        assert_eq!(dedent_raw(&["foo\n   bar"]), ["foo\nbar"]);
+    }
+    #[test]
+    fn test_dedent_lines_using_max_common_indent_1() {
+        assert_eq!(dedent_raw(&["foo\n  bar\n    baz"]), ["foo\nbar\n  baz"]);
+    }
+    #[test]
+    fn test_dedent_lines_using_max_common_indent_2() {
+        assert_eq!(dedent_raw(&["foo\n    bar\n  baz"]), ["foo\n  bar\nbaz"]);
+    }
+    #[test]
+    fn test_truncate_short_empty_line_in_the_middle() {
+        assert_eq!(
+            dedent_raw(&["foo\n \n  bar\n    baz"]),
+            ["foo\n\nbar\n  baz",]
+        );
+    }
+    #[test]
+    fn test_truncate_short_empty_line_in_the_last() {
+        assert_eq!(
+            dedent_raw(&["foo\n  bar\n    baz\n "]),
+            ["foo\nbar\n  baz\n",]
+        );
+    }
+    #[test]
+    fn test_regard_substitution_as_a_content() {
+        assert_eq!(
+            dedent_raw(&["foo\n ", "\n  bar\n    baz"]),
+            ["foo\n", "\n bar\n   baz",]
+        );
+    }
+    #[test]
+    fn test_dedent_long_empty_line() {
+        assert_eq!(
+            dedent_raw(&["foo\n   \n  bar\n    baz"]),
+            ["foo\n \nbar\n  baz",]
+        );
+    }
+    #[test]
+    fn test_substitution_indent() {
+        let options = DedentOptions::default();
+        for (elem, at_line_start, expected) in [
+            ("foo\n  ", false, Some("  ")),
+            ("foo\n\t", false, Some("\t")),
+            ("foo\n", false, Some("")),
+            ("foo\u{2028} ", false, Some(" ")),
+            ("foo\n  - ", false, Some("    ")),
+            ("foo\n\t- ", false, Some("\t  ")),
+            ("  ", false, None),
+            ("  ", true, Some("  ")),
+            ("- ", true, Some("  ")),
+            ("foo\\\n  ", false, Some("  ")),
+            ("foo\r\n  ", false, Some("  ")),
+        ] {
+            assert_eq!(
+                substitution_indent(elem, at_line_start, &options).as_deref(),
+                expected,
+                "{:?}",
+                elem
+            );
+        }
+    }
+    #[test]
+    fn test_substitution_indent_in_columns() {
+        let options = DedentOptions {
+            tab_width: NonZeroUsize::new(4),
+            ..Default::default()
+        };
+        assert_eq!(
+            substitution_indent("x\n\t-\t", false, &options).as_deref(),
+            Some("\t    ")
+        );
+    }
+    #[test]
+    fn test_dedent_after_crlf() {
+        assert_eq!(
+            dedent_raw(&["foo\r\n  bar\r\n    baz\r  qux"]),
+            ["foo\nbar\n  baz\nqux"]
+        );
+    }
+    #[test]
+    fn test_preserve_line_terminators() {
+        let options = DedentOptions {
+            preserve_line_terminators: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            dedent_raw_with(&["foo\r\n  bar\r\n    baz\r  qux\n  "], &options),
+            ["foo\r\nbar\r\n  baz\rqux\n"]
+        );
+    }
+    #[test]
+    fn test_measure_tabs_in_columns() {
+        let options = DedentOptions {
+            tab_width: NonZeroUsize::new(4),
+            ..Default::default()
+        };
+        assert_eq!(
+            dedent_raw_with(&["x\n\tfoo\n    bar\n  \tbaz"], &options),
+            ["x\nfoo\nbar\nbaz"]
+        );
+        assert_eq!(
+            dedent_raw_with(&["x\n\t\tfoo\n      bar"], &options),
+            ["x\n  foo\nbar"]
+        );
+    }
+    #[test]
+    fn test_fill_straddling_tabs_with_spaces() {
+        let options = DedentOptions {
+            tab_width: NonZeroUsize::new(8),
+            ..Default::default()
+        };
+        assert_eq!(
+            dedent_raw_with(&["x\n  foo\n\tbar\n \t\tbaz"], &options),
+            ["x\nfoo\n      bar\n      \tbaz"]
+        );
+    }
+    #[test]
+    fn test_offset_map() {
+        let (dedented, maps) =
+            dedent_raw_mapped(&["foo\n    bar\n      baz\n  "], &Default::default());
+        assert_eq!(dedented, ["foo\nbar\n  baz\n"]);
+        assert_eq!(
+            maps[0].edits(),
+            [
+                Edit {
+                    original: 4..8,
+                    dedented: 4..4
+                },
+                Edit {
+                    original: 12..16,
+                    dedented: 8..8
+                },
+                Edit {
+                    original: 22..24,
+                    dedented: 14..14
+                },
+            ]
+        );
+        let (_, maps_x) = dedent_raw_mapped(&["x\ny"], &Default::default());
+        assert_eq!(maps_x[0].edits(), []);
+        for (offset, expected) in [(3, 3), (4, 8), (8, 16), (10, 18), (14, 24)] {
+            assert_eq!(maps[0].to_original(offset), expected);
+            assert_eq!(
+                "foo\n    bar\n      baz\n  ".as_bytes().get(expected),
+                dedented[0].as_bytes().get(offset)
+            );
+        }
+        for (offset, expected) in [(3, 3), (6, 4), (8, 4), (14, 8), (18, 10), (23, 14)] {
+            assert_eq!(maps[0].to_dedented(offset), expected);
+        }
+    }
+    #[test]
+    fn test_offset_map_with_normalized_newlines() {
+        let (dedented, maps) =
+            dedent_raw_mapped(&["foo\r\n    bar\r\n      baz"], &Default::default());
+        assert_eq!(dedented, ["foo\nbar\n  baz"]);
+        for (offset, expected) in [(3, 4), (4, 9), (7, 13), (8, 18), (10, 20)] {
+            assert_eq!(maps[0].to_original(offset), expected);
+        }
+        for (offset, expected) in [(3, 3), (4, 3), (9, 4), (13, 7), (20, 10)] {
+            assert_eq!(maps[0].to_dedented(offset), expected);
+        }
+        for (offset, expected) in [(0, 0), (3, 3), (4, 5), (7, 12), (8, 14)] {
+            assert_eq!(maps[0].to_original_end(offset), expected);
+        }
+    }
+    #[test]
+    fn test_offset_map_with_filled_tabs() {
+        let options = DedentOptions {
+            tab_width: NonZeroUsize::new(4),
+            ..Default::default()
+        };
+        let (dedented, maps) = dedent_raw_mapped(&["x\n  foo\n\tbar"], &options);
+        assert_eq!(dedented, ["x\nfoo\n  bar"]);
+        for (offset, expected) in [(2, 4), (5, 7), (6, 8), (7, 8), (8, 9), (10, 11)] {
+            assert_eq!(maps[0].to_original(offset), expected);
+        }
+        for (offset, expected) in [(3, 2), (4, 2), (8, 7), (9, 8)] {
+            assert_eq!(maps[0].to_dedented(offset), expected);
+        }
+    }
+    #[test]
+    fn test_find_inconsistent_indents() {
+        let options = DedentOptions::default();
+        assert_eq!(
+            find_inconsistent_indents(&["x\n  foo\n\t\tbar\n  ", "\n \tbaz\n"], &options),
+            [
+                InconsistentIndent {
+                    index: 0,
+                    line: 8..13,
+                },
+                InconsistentIndent {
+                    index: 1,
+                    line: 1..6,
+                },
+            ]
+        );
+        assert_eq!(
+            find_inconsistent_indents(&["x\n\tfoo\n\t\tbar\n\n"], &options),
+            []
+        );
+        assert_eq!(
+            find_inconsistent_indents(&["x\n  foo\n\n \n\t\n  "], &options),
+            [InconsistentIndent {
+                index: 0,
+                line: 11..12,
+            }]
+        );
+        assert_eq!(find_inconsistent_indents(&["x\nfoo\n\tbar"], &options), []);
+    }
+    #[test]
+    fn test_find_inconsistent_indents_by_columns() {
+        let options = DedentOptions {
+            tab_width: NonZeroUsize::new(4),
+            ..Default::default()
+        };
+        assert_eq!(
+            find_inconsistent_indents(&["x\n\tfoo\n    bar\n\tbaz"], &options),
+            [InconsistentIndent {
+                index: 0,
+                line: 7..14,
+            }]
+        );
+    }
+    #[test]
+    fn test_dedent_ecmascript_whitespace() {
+        let options = DedentOptions {
+            whitespace: Whitespace::EcmaScript,
+            ..Default::default()
+        };
+        assert_eq!(
+            dedent_raw_with(
+                &["x\n\u{3000}\u{3000}foo\n\u{3000}\u{3000}\u{3000}bar"],
+                &options
+            ),
+            ["x\nfoo\n\u{3000}bar"]
+        );
+        assert_eq!(
+            dedent_raw_with(&["x\n\u{A0} foo\n\u{FEFF} bar"], &options),
+            ["x\nfoo\nbar"]
+        );
+        // Not indentation by default
+        assert_eq!(
+            dedent_raw(&["x\n \u{3000}foo\n  bar"]),
+            ["x\n\u{3000}foo\n bar"]
+        );
+    }
+    #[test]
+    fn test_dedent_custom_whitespace() {
+        let options = DedentOptions {
+            whitespace: Whitespace::Custom(vec!['\u{3000}', ' ', '\n']),
+            ..Default::default()
+        };
+        assert_eq!(
+            dedent_raw_with(&["x\n\u{3000} foo\n\u{3000}\u{3000}\tbar\n\n"], &options),
+            ["x\nfoo\n\tbar\n\n"]
+        );
+    }
+    #[test]
+    fn test_analyze_and_map_unicode_whitespace() {
+        let options = DedentOptions {
+            whitespace: Whitespace::EcmaScript,
+            ..Default::default()
+        };
+        let orig = "x\n\u{3000}foo\n bar";
+        assert_eq!(
+            find_inconsistent_indents(&[orig], &options),
+            [InconsistentIndent {
+                index: 0,
+                line: 9..13,
+            }]
+        );
+        let raw = [orig];
+        let (dedented, maps) = dedent_raw_mapped(&raw, &options);
+        assert_eq!(dedented, ["x\nfoo\nbar"]);
+        assert_eq!(maps[0].to_original(2), 5);
+        assert_eq!(maps[0].to_original(6), 10);
+        assert_eq!(
+            substitution_indent("x\n\u{3000}- ", false, &options).as_deref(),
+            Some("\u{3000}  ")
+        );
+    }
+    #[test]
+    fn test_borrow_unchanged_elements() {
+        let dedented = dedent_raw(&["foo\n  bar", "\n  baz ", "qux"]);
+        assert_eq!(dedented, ["foo\nbar", "\nbaz ", "qux"]);
+        assert!(matches!(dedented[0], Cow::Owned(_)));
+        assert!(matches!(dedented[2], Cow::Borrowed(_)));
+        assert!(dedent_raw(&["foo\nbar\n\tbaz"])
+            .iter()
+            .all(|elem| matches!(elem, Cow::Borrowed(_))));
+        assert!(matches!(dedent_raw(&["foo\r\nbar"])[0], Cow::Owned(_)));
+    }
+    #[test]
+    fn test_find_newlines() {
+        let s = "a\u{2028}b\u{2029}c\u{2027}\u{3000}d\r\ne\rf\n";
+        let mut newlines = Vec::new();
+        let mut last = 0;
+        while let Some(newline) = find_newline(s, last) {
+            newlines.push(&s[newline.clone()]);
+            last = newline.end;
+        }
+        assert_eq!(newlines, ["\u{2028}", "\u{2029}", "\r\n", "\r", "\n"]);
+    }
+    #[test]
+    fn test_dedent_the_second_line_and_later_at_infinity_if_they_are_all_empty() {
+        assert_eq!(
+            dedent_raw(&["x\n  \u{2028}\t\n   \u{2029}\t\t"]),
+            ["x\n\u{2028}\n\u{2029}",]
+        );
+    }
+}
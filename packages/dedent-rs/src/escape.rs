@@ -0,0 +1,312 @@
+use std::iter::Peekable;
+
+use crate::cook::{cook_js_string, EscapeError, Grammar, JsString};
+
+/**
+ * Options for [`escape_str_into`] and [`escape_js_string`].
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EscapeOptions {
+    /// The kind of literal to encode for.
+    pub target: EscapeTarget,
+    /// How to encode characters outside ASCII.
+    pub non_ascii: NonAsciiPolicy,
+    /// How to encode lone surrogates.
+    pub lone_surrogates: LoneSurrogatePolicy,
+}
+
+/// The kind of literal the escaped text is placed in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EscapeTarget {
+    /// The raw text of a template literal, between `` ` `` or `}` and `` ` `` or `${`
+    #[default]
+    Template,
+    /// The contents of a `"`-quoted string literal
+    DoubleQuoted,
+    /// The contents of a `'`-quoted string literal
+    SingleQuoted,
+}
+
+/// How to encode characters outside ASCII.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NonAsciiPolicy {
+    /// Emit the characters as they are.
+    #[default]
+    Keep,
+    /// Emit `\uXXXX`, using surrogate pairs for astral characters so that the
+    /// result is also valid in ES5 string literals.
+    Escape,
+}
+
+/// How to encode lone surrogates, which have no representation other than escapes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LoneSurrogatePolicy {
+    /// Emit `\uXXXX`, preserving the value.
+    #[default]
+    Escape,
+    /// Replace them with U+FFFD REPLACEMENT CHARACTER.
+    Replace,
+}
+
+/**
+ * Rewrites `raw` into the shortest raw text with the same cooked value, dropping
+ * redundant escapes such as `\'`, `\%` and `\x41` and line continuations.
+ */
+pub fn normalize_raw(raw: &str) -> Result<String, EscapeError> {
+    Ok(escape_js_string(
+        &cook_js_string(raw, Grammar::Template)?,
+        &EscapeOptions::default(),
+    ))
+}
+
+/**
+ * Encodes `value` as the raw text of a literal, so that cooking the result yields
+ * `value` back.
+ */
+pub fn escape_js_string(value: &JsString, options: &EscapeOptions) -> String {
+    let mut buf = String::new();
+    match value {
+        JsString::Utf8(s) => escape_chars(&mut buf, s.chars().map(Ok), "", options),
+        JsString::Utf16(units) => escape_chars(
+            &mut buf,
+            char::decode_utf16(units.iter().copied())
+                .map(|r| r.map_err(|e| e.unpaired_surrogate())),
+            "",
+            options,
+        ),
+    }
+    buf
+}
+
+/**
+ * Appends `value` to `raw` so that it is cooked back into `value`,
+ * taking care not to form a different token with the surrounding raw text.
+ */
+pub fn escape_str_into(raw: &mut String, value: &str, next_raw: &str, options: &EscapeOptions) {
+    escape_chars(raw, value.chars().map(Ok), next_raw, options);
+}
+
+/// Escapes a sequence of characters, where `Err` denotes a lone surrogate.
+fn escape_chars<I>(raw: &mut String, chars: I, next_raw: &str, options: &EscapeOptions)
+where
+    I: Iterator<Item = Result<char, u16>>,
+{
+    let template = options.target == EscapeTarget::Template;
+    let mut chars = chars.peekable();
+    let mut first = true;
+    while let Some(ch) = chars.next() {
+        let ch = match ch {
+            Ok(ch) => ch,
+            Err(unit) => match options.lone_surrogates {
+                LoneSurrogatePolicy::Escape => {
+                    push_unicode_escape(raw, unit);
+                    first = false;
+                    continue;
+                }
+                LoneSurrogatePolicy::Replace => '\u{FFFD}',
+            },
+        };
+        match ch {
+            '\\' => raw.push_str("\\\\"),
+            '`' if template => raw.push_str("\\`"),
+            '"' if options.target == EscapeTarget::DoubleQuoted => raw.push_str("\\\""),
+            '\'' if options.target == EscapeTarget::SingleQuoted => raw.push_str("\\'"),
+            '\n' if !template => raw.push_str("\\n"),
+            '\r' => raw.push_str("\\r"),
+            '\x08' => raw.push_str("\\b"),
+            '\x0B' => raw.push_str("\\v"),
+            '\x0C' => raw.push_str("\\f"),
+            '\0' => {
+                let next = peek_next(&mut chars, next_raw);
+                if next.map_or(false, |next| next.is_ascii_digit()) {
+                    raw.push_str("\\x00");
+                } else {
+                    raw.push_str("\\0");
+                }
+            }
+            '\t' | '\n' => raw.push(ch),
+            '\0'..='\x1F' => raw.push_str(&format!("\\x{:02X}", ch as u32)),
+            // Line terminators for dedent, and in string literals before ES2019
+            '\u{2028}' | '\u{2029}' => push_unicode_escape(raw, ch as u16),
+            '$' if template => {
+                let next = peek_next(&mut chars, next_raw);
+                if next == Some('{') {
+                    raw.push_str("\\$");
+                } else {
+                    raw.push('$');
+                }
+            }
+            // `$` + `{`
+            '{' if template && first && ends_with_unescaped(raw, '$') => raw.push_str("\\{"),
+            // `\0` + digit
+            '0'..='9' if first && ends_with_unescaped(raw, '0') => {
+                raw.push_str(&format!("\\x{:02X}", ch as u32));
+            }
+            _ if !ch.is_ascii() && options.non_ascii == NonAsciiPolicy::Escape => {
+                for unit in ch.encode_utf16(&mut [0; 2]) {
+                    push_unicode_escape(raw, *unit);
+                }
+            }
+            _ => raw.push(ch),
+        }
+        first = false;
+    }
+}
+
+/// Returns the character following the current one, possibly in the raw text after `value`.
+fn peek_next<I>(chars: &mut Peekable<I>, next_raw: &str) -> Option<char>
+where
+    I: Iterator<Item = Result<char, u16>>,
+{
+    match chars.peek() {
+        Some(next) => next.ok(),
+        None => next_raw.chars().next(),
+    }
+}
+
+fn push_unicode_escape(raw: &mut String, unit: u16) {
+    raw.push_str(&format!("\\u{:04X}", unit));
+}
+
+/// Does `raw` end with `ch`, preceded by an even number of backslashes?
+/// For `'0'`, checks the opposite: that `\0` is an escape.
+fn ends_with_unescaped(raw: &str, ch: char) -> bool {
+    let Some(rest) = raw.strip_suffix(ch) else {
+        return false;
+    };
+    let backslashes = rest.len() - rest.trim_end_matches('\\').len();
+    if ch == '0' {
+        backslashes % 2 == 1
+    } else {
+        backslashes % 2 == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escaped(prev_raw: &str, value: &str, next_raw: &str) -> String {
+        let mut raw = prev_raw.to_owned();
+        escape_str_into(&mut raw, value, next_raw, &EscapeOptions::default());
+        raw.push_str(next_raw);
+        raw
+    }
+
+    fn escaped_with(value: &JsString, options: EscapeOptions) -> String {
+        escape_js_string(value, &options)
+    }
+
+    #[test]
+    fn test_escape_special_characters() {
+        assert_eq!(escaped("", "a\\b`c\rd", ""), "a\\\\b\\`c\\rd");
+        assert_eq!(escaped("", "a\nb\tc", ""), "a\nb\tc");
+        assert_eq!(
+            escaped("", "\x08\x0B\x0C\x1B\x7F", ""),
+            "\\b\\v\\f\\x1B\x7F"
+        );
+        assert_eq!(escaped("", "a\u{2028}b\u{2029}", ""), "a\\u2028b\\u2029");
+    }
+
+    #[test]
+    fn test_escape_substitution_openers() {
+        assert_eq!(escaped("", "${a}", ""), "\\${a}");
+        assert_eq!(escaped("", "a$", "{b"), "a\\${b");
+        assert_eq!(escaped("$", "{a}", ""), "$\\{a}");
+        assert_eq!(escaped("\\$", "{a}", ""), "\\${a}");
+        assert_eq!(escaped("", "$a", ""), "$a");
+    }
+
+    #[test]
+    fn test_escape_digits_after_null_escape() {
+        assert_eq!(escaped("\\0", "12", ""), "\\0\\x312");
+        assert_eq!(escaped("\\\\0", "12", ""), "\\\\012");
+        assert_eq!(escaped("", "\0", "1"), "\\x001");
+        assert_eq!(escaped("", "\0\01", ""), "\\0\\x001");
+        assert_eq!(escaped("", "\0", "a"), "\\0a");
+    }
+
+    #[test]
+    fn test_escape_string_literals() {
+        let value = JsString::Utf8("'\"`${a}\n\\".to_owned());
+        let double = EscapeOptions {
+            target: EscapeTarget::DoubleQuoted,
+            ..Default::default()
+        };
+        let single = EscapeOptions {
+            target: EscapeTarget::SingleQuoted,
+            ..Default::default()
+        };
+        assert_eq!(escaped_with(&value, double), "'\\\"`${a}\\n\\\\");
+        assert_eq!(escaped_with(&value, single), "\\'\"`${a}\\n\\\\");
+    }
+
+    #[test]
+    fn test_escape_non_ascii() {
+        let value = JsString::Utf8("é😀".to_owned());
+        assert_eq!(escaped_with(&value, Default::default()), "é😀");
+        let options = EscapeOptions {
+            non_ascii: NonAsciiPolicy::Escape,
+            ..Default::default()
+        };
+        assert_eq!(escaped_with(&value, options), "\\u00E9\\uD83D\\uDE00");
+    }
+
+    #[test]
+    fn test_escape_lone_surrogates() {
+        let value = JsString::Utf16(vec![0x61, 0xD83D, 0x62]);
+        assert_eq!(escaped_with(&value, Default::default()), "a\\uD83Db");
+        let options = EscapeOptions {
+            lone_surrogates: LoneSurrogatePolicy::Replace,
+            ..Default::default()
+        };
+        assert_eq!(escaped_with(&value, options), "a\u{FFFD}b");
+    }
+
+    #[test]
+    fn test_round_trip_through_cook() {
+        let values = [
+            JsString::Utf8(String::new()),
+            JsString::Utf8("foo\n  bar\tbaz".to_owned()),
+            JsString::Utf8("`${a}` $ { } \\ \\n \r\n \0 \x001 \x7F".to_owned()),
+            JsString::Utf8("\u{2028}\u{2029}\u{FEFF}é😀".to_owned()),
+            JsString::Utf16(vec![0xDE00, 0xD83D, 0x24, 0xD83D]),
+        ];
+        for value in &values {
+            for non_ascii in [NonAsciiPolicy::Keep, NonAsciiPolicy::Escape] {
+                let options = EscapeOptions {
+                    non_ascii,
+                    ..Default::default()
+                };
+                let raw = escape_js_string(value, &options);
+                assert_eq!(
+                    &cook_js_string(&raw, Grammar::Template).unwrap(),
+                    value,
+                    "raw: {:?}",
+                    raw
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_normalize_raw() {
+        assert_eq!(
+            normalize_raw("\\' \\\" \\% \\u{0000041} \\x41 \\u0042").unwrap(),
+            "' \" % A A B"
+        );
+        assert_eq!(normalize_raw("\\\nfoo\\n\\t").unwrap(), "foo\n\t");
+        assert_eq!(
+            normalize_raw("\\` \\${ \\$ \\\\").unwrap(),
+            "\\` \\${ $ \\\\"
+        );
+        assert_eq!(
+            normalize_raw("\\uD83D\\uDE00 \\uD83D").unwrap(),
+            "😀 \\uD83D"
+        );
+        assert!(normalize_raw("\\9").is_err());
+    }
+}
@@ -0,0 +1,36 @@
+//! The algorithm behind [`@qnighy/dedent`](https://github.com/qnighy/dedent-js),
+//! shared with the SWC plugin so that Rust code gets byte-identical results.
+//!
+//! `` dedent`...` `` works in two steps: [`dedent_raw()`] removes the common indentation
+//! from the raw texts of the template, and [`cook()`] evaluates the escape sequences in
+//! each of them.
+//!
+//! ```
+//! use qnighy_dedent::{cook, dedent_raw};
+//!
+//! let raw = dedent_raw(&["\\\n  Hello,\n    ", "\\x21\n"]);
+//! assert_eq!(raw, ["\\\nHello,\n  ", "\\x21\n"]);
+//! assert_eq!(cook(&raw[0]).unwrap().as_deref(), Some("Hello,\n  "));
+//! assert_eq!(cook(&raw[1]).unwrap().as_deref(), Some("!\n"));
+//! ```
+//!
+//! [`escape_str_into`] and [`escape_js_string`] go the other way, producing raw text
+//! from a value.
+
+#![warn(missing_docs)]
+
+mod cook;
+mod dedent_raw;
+mod escape;
+
+pub use crate::cook::{
+    cook, cook_best_effort, cook_js_string, EscapeError, EscapeErrorKind, Grammar, JsString,
+};
+pub use crate::dedent_raw::{
+    dedent_raw, dedent_raw_mapped, dedent_raw_with, find_inconsistent_indents, substitution_indent,
+    DedentOptions, Edit, InconsistentIndent, OffsetMap, Whitespace,
+};
+pub use crate::escape::{
+    escape_js_string, escape_str_into, normalize_raw, EscapeOptions, EscapeTarget,
+    LoneSurrogatePolicy, NonAsciiPolicy,
+};
@@ -0,0 +1,158 @@
+use std::num::NonZeroUsize;
+
+use qnighy_dedent::{DedentOptions, Whitespace};
+use serde::{Deserialize, Deserializer};
+
+/// Plugin configuration, passed as JSON through the plugin metadata.
+///
+/// ```json
+/// {
+///   "importSources": ["@qnighy/dedent", "@acme/text"],
+///   "functionNames": ["dedent", "outdent"]
+/// }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub(crate) struct Config {
+    /// Module specifiers from which the dedent function is imported.
+    pub(crate) import_sources: Vec<String>,
+    /// Exported names to be treated as the dedent function.
+    pub(crate) function_names: Vec<String>,
+    /// Fails the build if any reference to the dedent function remains after the transformation.
+    pub(crate) no_runtime: bool,
+    /// How `dedent`...`` is emitted.
+    pub(crate) output: OutputMode,
+    /// Indents the continuation lines of multi-line constant substitutions
+    /// to the column of the substitution.
+    pub(crate) reindent_substitutions: bool,
+    /// Rewrites the raw text of untagged templates to use as few escapes as possible.
+    pub(crate) normalize_escapes: bool,
+    /// Measures indentation in columns with this tab stop, instead of counting tabs as one.
+    pub(crate) tab_width: Option<NonZeroUsize>,
+    /// Characters that make up indentation: `"ascii"`, `"ecmascript"` or a list of characters.
+    #[serde(deserialize_with = "deserialize_whitespace")]
+    pub(crate) whitespace: Whitespace,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum OutputMode {
+    /// Always emit a template literal.
+    #[default]
+    Template,
+    /// Emit a string literal if there are no substitutions.
+    String,
+    /// In addition to `String`, emit `"...".concat(...)` if there are substitutions.
+    Es5,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WhitespaceOption {
+    Class(WhitespaceClass),
+    Custom(Vec<char>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum WhitespaceClass {
+    Ascii,
+    Ecmascript,
+}
+
+fn deserialize_whitespace<'de, D>(deserializer: D) -> Result<Whitespace, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match WhitespaceOption::deserialize(deserializer)? {
+        WhitespaceOption::Class(WhitespaceClass::Ascii) => Whitespace::Ascii,
+        WhitespaceOption::Class(WhitespaceClass::Ecmascript) => Whitespace::EcmaScript,
+        WhitespaceOption::Custom(chars) => Whitespace::Custom(chars),
+    })
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            import_sources: vec!["@qnighy/dedent".to_owned()],
+            function_names: vec!["dedent".to_owned()],
+            no_runtime: false,
+            output: OutputMode::Template,
+            reindent_substitutions: false,
+            normalize_escapes: false,
+            tab_width: None,
+            whitespace: Whitespace::Ascii,
+        }
+    }
+}
+
+impl Config {
+    pub(crate) fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub(crate) fn dedent_options(&self) -> DedentOptions {
+        DedentOptions {
+            tab_width: self.tab_width,
+            whitespace: self.whitespace.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_for_empty_object() {
+        let config = Config::from_json("{}").unwrap();
+        assert_eq!(config.import_sources, ["@qnighy/dedent"]);
+        assert_eq!(config.function_names, ["dedent"]);
+    }
+
+    #[test]
+    fn test_parse_custom_sources_and_names() {
+        let config = Config::from_json(
+            r#"{"importSources": ["@acme/text"], "functionNames": ["dedent", "outdent"]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.import_sources, ["@acme/text"]);
+        assert_eq!(config.function_names, ["dedent", "outdent"]);
+    }
+
+    #[test]
+    fn test_parse_output_mode() {
+        let config = Config::from_json(r#"{"output": "es5"}"#).unwrap();
+        assert_eq!(config.output, OutputMode::Es5);
+        assert!(Config::from_json(r#"{"output": "es3"}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_tab_width() {
+        let config = Config::from_json(r#"{"tabWidth": 4}"#).unwrap();
+        assert_eq!(config.tab_width, NonZeroUsize::new(4));
+        assert!(Config::from_json(r#"{"tabWidth": 0}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_whitespace() {
+        let parse = |json| Config::from_json(json).map(|config| config.whitespace);
+        assert_eq!(parse("{}").unwrap(), Whitespace::Ascii);
+        assert_eq!(
+            parse(r#"{"whitespace": "ecmascript"}"#).unwrap(),
+            Whitespace::EcmaScript
+        );
+        assert_eq!(
+            parse(r#"{"whitespace": [" ", "\u3000"]}"#).unwrap(),
+            Whitespace::Custom(vec![' ', '\u{3000}'])
+        );
+        assert!(parse(r#"{"whitespace": "unicode"}"#).is_err());
+        assert!(parse(r#"{"whitespace": ["ab"]}"#).is_err());
+    }
+
+    #[test]
+    fn test_reject_unknown_fields() {
+        assert!(Config::from_json(r#"{"importSource": "@acme/text"}"#).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use swc_core::common::errors::{DiagnosticId, Handler};
+use swc_core::common::Span;
+
+/// A problem found at a dedent call site, reported after the transformation.
+#[derive(Debug, Clone)]
+pub(crate) struct Diagnostic {
+    pub(crate) severity: Severity,
+    pub(crate) reason: Reason,
+    pub(crate) span: Span,
+    pub(crate) message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Severity {
+    /// The call site is left as is; it works at runtime but is not optimized.
+    Warning,
+    /// The call site is guaranteed to throw at runtime.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Reason {
+    /// `dedent`...`` contains an escape sequence that cannot be cooked.
+    InvalidEscape,
+    /// `dedent(...)`...`` does not have exactly one argument.
+    WrapperArity,
+    /// `dedent(...tags)`...``
+    WrapperSpread,
+    /// `dedent("...")` or `dedent(`...`)`
+    StringArgument,
+    /// The tag refers to dedent in a way the plugin does not understand.
+    UnsupportedTag,
+    /// Some lines of `dedent`...`` are indented with different characters than the others.
+    MixedIndent,
+    /// dedent or its module is still referenced after the transformation, under noRuntime.
+    RuntimeReference,
+}
+
+impl Reason {
+    pub(crate) fn code(self) -> &'static str {
+        match self {
+            Reason::InvalidEscape => "dedent/invalid-escape",
+            Reason::WrapperArity => "dedent/wrapper-arity",
+            Reason::WrapperSpread => "dedent/wrapper-spread",
+            Reason::StringArgument => "dedent/string-argument",
+            Reason::UnsupportedTag => "dedent/unsupported-tag",
+            Reason::MixedIndent => "dedent/mixed-indent",
+            Reason::RuntimeReference => "dedent/runtime-reference",
+        }
+    }
+}
+
+impl Diagnostic {
+    pub(crate) fn warning(reason: Reason, span: Span, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            reason,
+            span,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn error(reason: Reason, span: Span, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            reason,
+            span,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn emit(&self, handler: &Handler) {
+        let code = DiagnosticId::Error(self.reason.code().to_owned());
+        match self.severity {
+            Severity::Warning => handler
+                .struct_span_warn_with_code(self.span, &self.message, code)
+                .emit(),
+            Severity::Error => handler
+                .struct_span_err_with_code(self.span, &self.message, code)
+                .emit(),
+        }
+    }
+}
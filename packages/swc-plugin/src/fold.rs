@@ -0,0 +1,223 @@
+use std::mem;
+
+use qnighy_dedent::{
+    cook, cook_js_string, escape_str_into, substitution_indent, DedentOptions, EscapeOptions,
+    Grammar,
+};
+use swc_core::common::SyntaxContext;
+use swc_core::ecma::ast::{Expr, Lit, Str, Tpl, TplElement, UnaryOp};
+use swc_core::ecma::atoms::Atom;
+
+/**
+ * Merges substitutions whose values are known at compile time into the adjacent quasis.
+ *
+ * Only meaningful for untagged templates, where the quasis and the substitutions are
+ * not observable separately.
+ *
+ * If `reindent` is set, multi-line values have their continuation lines indented
+ * to the column where the substitution appears, measured as `options` tells.
+ *
+ * `unresolved_ctxt` is the syntax context of global references such as `undefined`.
+ */
+pub(crate) fn fold_constants(
+    tpl: &mut Tpl,
+    reindent: bool,
+    options: &DedentOptions,
+    unresolved_ctxt: SyntaxContext,
+) {
+    if tpl.quasis.iter().any(|elem| elem.cooked.is_none()) {
+        return;
+    }
+    let mut quasis = mem::take(&mut tpl.quasis).into_iter();
+    let mut new_quasis: Vec<TplElement> = vec![quasis.next().unwrap()];
+    let mut new_exprs = Vec::with_capacity(tpl.exprs.len());
+    for (expr, quasi) in mem::take(&mut tpl.exprs).into_iter().zip(quasis) {
+        let Some(mut value) = to_js_string(&expr, unresolved_ctxt) else {
+            new_exprs.push(expr);
+            new_quasis.push(quasi);
+            continue;
+        };
+        let at_line_start = new_quasis.len() == 1;
+        let last = new_quasis.last_mut().unwrap();
+        if reindent {
+            if let Some(indent) = substitution_indent(&last.raw, at_line_start, options) {
+                value = indent_continuation_lines(&value, &indent);
+            }
+        }
+        let mut raw = String::from(&*last.raw);
+        let mut cooked = String::from(&**last.cooked.as_ref().unwrap());
+        escape_str_into(&mut raw, &value, &quasi.raw, &EscapeOptions::default());
+        raw.push_str(&quasi.raw);
+        cooked.push_str(&value);
+        cooked.push_str(quasi.cooked.as_ref().unwrap());
+        last.raw = Atom::new(raw);
+        last.cooked = Some(Atom::new(cooked));
+        last.span = last.span.to(quasi.span);
+        last.tail = quasi.tail;
+    }
+    tpl.quasis = new_quasis;
+    tpl.exprs = new_exprs;
+}
+
+/**
+ * Evaluates `String.raw`...`` if all the substitutions are literals.
+ */
+pub(crate) fn fold_string_raw(tpl: &Tpl, unresolved_ctxt: SyntaxContext) -> Option<String> {
+    let mut buf = String::from(&*tpl.quasis[0].raw);
+    for (expr, quasi) in tpl.exprs.iter().zip(&tpl.quasis[1..]) {
+        buf.push_str(&to_js_string(expr, unresolved_ctxt)?);
+        buf.push_str(&quasi.raw);
+    }
+    Some(buf)
+}
+
+/**
+ * Prepends `indent` to each line of `value` except the first one and empty ones.
+ */
+fn indent_continuation_lines(value: &str, indent: &str) -> String {
+    let mut buf = String::with_capacity(value.len());
+    let mut lines = value.split_inclusive(['\n', '\u{2028}', '\u{2029}']);
+    buf.push_str(lines.next().unwrap_or(""));
+    for line in lines {
+        if !matches!(line, "\n" | "\u{2028}" | "\u{2029}") {
+            buf.push_str(indent);
+        }
+        buf.push_str(line);
+    }
+    buf
+}
+
+/**
+ * Evaluates `ToString(e)` if `e` is a literal.
+ */
+fn to_js_string(e: &Expr, unresolved_ctxt: SyntaxContext) -> Option<String> {
+    match e.unwrap_parens() {
+        Expr::Lit(Lit::Str(e)) => str_value(e),
+        Expr::Lit(Lit::Num(e)) => Some(number_to_string(e.value)),
+        Expr::Lit(Lit::Bool(e)) => Some(e.value.to_string()),
+        Expr::Lit(Lit::Null(_)) => Some("null".to_owned()),
+        Expr::Lit(Lit::BigInt(e)) => Some(e.value.to_string()),
+        Expr::Unary(e) if e.op == UnaryOp::Minus => match e.arg.unwrap_parens() {
+            Expr::Lit(Lit::Num(arg)) => Some(number_to_string(-arg.value)),
+            Expr::Lit(Lit::BigInt(arg)) => Some((-&*arg.value).to_string()),
+            _ => None,
+        },
+        Expr::Unary(e) if e.op == UnaryOp::Void && e.arg.unwrap_parens().is_lit() => {
+            Some("undefined".to_owned())
+        }
+        Expr::Ident(e) if &*e.sym == "undefined" && e.span.ctxt == unresolved_ctxt => {
+            Some("undefined".to_owned())
+        }
+        Expr::Tpl(e) if e.exprs.is_empty() => cook(&e.quasis[0].raw).ok()?,
+        _ => None,
+    }
+}
+
+/**
+ * Returns the value of a string literal, or `None` if it contains lone surrogates.
+ */
+fn str_value(e: &Str) -> Option<String> {
+    // swc keeps lone surrogates escaped in the value, indistinguishable from a backslash,
+    // so the value is recomputed from the raw text
+    let Some(raw) = &e.raw else {
+        return Some(e.value.to_string());
+    };
+    let raw = &raw[1..raw.len() - 1];
+    cook_js_string(raw, Grammar::SloppyString)
+        .ok()?
+        .into_string()
+}
+
+/**
+ * Implements `Number::toString(x)` with radix 10.
+ */
+pub(crate) fn number_to_string(x: f64) -> String {
+    if x.is_nan() {
+        return "NaN".to_owned();
+    } else if x == 0.0 {
+        // Including -0
+        return "0".to_owned();
+    } else if x.is_infinite() {
+        return if x > 0.0 { "Infinity" } else { "-Infinity" }.to_owned();
+    } else if x < 0.0 {
+        return format!("-{}", number_to_string(-x));
+    }
+    // Shortest round-tripping digits, as required by the spec
+    let sci = format!("{:e}", x);
+    let (mantissa, exponent) = sci.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().unwrap() + 1;
+    if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let sign = if n - 1 < 0 { '-' } else { '+' };
+        if k == 1 {
+            format!("{}e{}{}", digits, sign, (n - 1).abs())
+        } else {
+            format!(
+                "{}.{}e{}{}",
+                &digits[..1],
+                &digits[1..],
+                sign,
+                (n - 1).abs()
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_to_string_integers() {
+        assert_eq!(number_to_string(0.0), "0");
+        assert_eq!(number_to_string(-0.0), "0");
+        assert_eq!(number_to_string(3.0), "3");
+        assert_eq!(number_to_string(-42.0), "-42");
+        assert_eq!(number_to_string(1e20), "100000000000000000000");
+        assert_eq!(
+            number_to_string(123456789012345680000.0),
+            "123456789012345680000"
+        );
+    }
+
+    #[test]
+    fn test_number_to_string_fractions() {
+        assert_eq!(number_to_string(1.2), "1.2");
+        assert_eq!(number_to_string(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(number_to_string(0.000001), "0.000001");
+        assert_eq!(number_to_string(123.456), "123.456");
+    }
+
+    #[test]
+    fn test_number_to_string_exponents() {
+        assert_eq!(number_to_string(1e21), "1e+21");
+        assert_eq!(number_to_string(1.5e300), "1.5e+300");
+        assert_eq!(number_to_string(1e-7), "1e-7");
+        assert_eq!(number_to_string(-1.25e-7), "-1.25e-7");
+        assert_eq!(number_to_string(5e-324), "5e-324");
+    }
+
+    #[test]
+    fn test_number_to_string_special_values() {
+        assert_eq!(number_to_string(f64::NAN), "NaN");
+        assert_eq!(number_to_string(f64::INFINITY), "Infinity");
+        assert_eq!(number_to_string(f64::NEG_INFINITY), "-Infinity");
+    }
+
+    #[test]
+    fn test_indent_continuation_lines() {
+        assert_eq!(
+            indent_continuation_lines("a\nb\n\nc\n", "  "),
+            "a\n  b\n\n  c\n"
+        );
+        assert_eq!(indent_continuation_lines("a", "  "), "a");
+        assert_eq!(indent_continuation_lines("", "  "), "");
+    }
+}
@@ -1,121 +1,374 @@
-mod cook;
-mod dedent_raw;
+mod config;
+mod diagnostics;
+mod fold;
 
+use std::borrow::Cow;
 use std::collections::HashSet;
 use std::mem;
+use std::ops::Range;
 
+use qnighy_dedent::{
+    cook, cook_best_effort, dedent_raw_mapped, dedent_raw_with, find_inconsistent_indents,
+    normalize_raw, Grammar, InconsistentIndent, OffsetMap,
+};
 use swc_core::common::util::take::Take;
-use swc_core::common::DUMMY_SP;
+use swc_core::common::{BytePos, Mark, Span, SyntaxContext, DUMMY_SP};
 use swc_core::ecma::ast::{
-    Callee, Expr, ExprOrSpread, Id, Ident, ImportNamedSpecifier, ImportSpecifier, Lit, MemberProp,
-    Module, ModuleDecl, ModuleExportName, ModuleItem, Number, Program, SeqExpr, TaggedTpl,
+    BlockStmt, CallExpr, Callee, ComputedPropName, Decl, ExportAll, Expr, ExprOrSpread, ExprStmt,
+    FnExpr, Function, Id, Ident, ImportDecl, ImportNamedSpecifier, ImportSpecifier, Lit,
+    MemberExpr, MemberProp, Module, ModuleDecl, ModuleExportName, ModuleItem, NamedExport, NewExpr,
+    Number, ObjectPatProp, Pat, Program, PropName, Script, SeqExpr, Stmt, Str, TaggedTpl,
+    ThrowStmt, Tpl, TplElement, TsImportEqualsDecl, TsModuleRef, VarDeclarator,
 };
 use swc_core::ecma::atoms::{Atom, JsWord};
-use swc_core::ecma::visit::{as_folder, FoldWith, Visit, VisitMut, VisitMutWith};
+use swc_core::ecma::visit::{as_folder, FoldWith, Visit, VisitMut, VisitMutWith, VisitWith};
+use swc_core::plugin::errors::HANDLER;
 use swc_core::plugin::{plugin_transform, proxies::TransformPluginProgramMetadata};
 
-use crate::cook::cook;
-use crate::dedent_raw::dedent_raw;
+use crate::config::{Config, OutputMode};
+use crate::diagnostics::{Diagnostic, Reason};
+use crate::fold::{fold_constants, fold_string_raw};
 
 #[plugin_transform]
-pub fn process_transform(program: Program, _metadata: TransformPluginProgramMetadata) -> Program {
-    program.fold_with(&mut as_folder(MainVisitor::new()))
+pub fn process_transform(program: Program, metadata: TransformPluginProgramMetadata) -> Program {
+    let config = metadata
+        .get_transform_plugin_config()
+        .map(|json| Config::from_json(&json).expect("invalid config for @qnighy/swc-plugin-dedent"))
+        .unwrap_or_default();
+    let mut v = MainVisitor::with_config(&config).with_unresolved_mark(metadata.unresolved_mark);
+    let program = program.fold_with(&mut as_folder(&mut v));
+    HANDLER.with(|handler| {
+        for diagnostic in &v.diagnostics {
+            diagnostic.emit(handler);
+        }
+    });
+    program
 }
 
 struct MainVisitor {
     ids: Ids,
+    config: Config,
+    /// Syntax context of unresolved (global) references
+    unresolved_ctxt: SyntaxContext,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl MainVisitor {
+    #[cfg(test)]
     fn new() -> Self {
-        Self { ids: Ids::new() }
+        Self::with_config(&Config::default())
+    }
+
+    fn with_config(config: &Config) -> Self {
+        Self {
+            ids: Ids::new(config),
+            config: config.clone(),
+            unresolved_ctxt: SyntaxContext::empty(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn with_unresolved_mark(mut self, unresolved_mark: Mark) -> Self {
+        self.unresolved_ctxt = SyntaxContext::empty().apply_mark(unresolved_mark);
+        self
+    }
+
+    /// Transforms the dedent calls and returns the imported ids that are no longer in use.
+    fn transform<N>(&mut self, n: &mut N, imports: Imports) -> HashSet<Id>
+    where
+        N: VisitMutWith<TransformVisitor> + VisitWith<FindReferenceVisitor>,
+    {
+        let mut v = TransformVisitor::new(
+            self.ids.clone(),
+            self.config.clone(),
+            self.unresolved_ctxt,
+            imports.clone(),
+        );
+        n.visit_mut_with(&mut v);
+        self.diagnostics.append(&mut v.diagnostics);
+
+        let mut v = FindReferenceVisitor::new(
+            self.ids.clone(),
+            self.unresolved_ctxt,
+            imports,
+            v.removable_ids,
+        );
+        n.visit_with(&mut v);
+        if self.config.no_runtime {
+            for &span in &v.residual_references {
+                self.diagnostics.push(Diagnostic::error(
+                    Reason::RuntimeReference,
+                    span,
+                    "dedent is referenced at runtime, which is disallowed by the noRuntime option",
+                ));
+            }
+        }
+        v.removable_ids
+    }
+
+    /// Reports the imports of dedent that survived the transformation, under the noRuntime option.
+    fn report_runtime_imports<N>(&mut self, n: &N)
+    where
+        N: VisitWith<RuntimeImportVisitor>,
+    {
+        if !self.config.no_runtime {
+            return;
+        }
+        let mut v = RuntimeImportVisitor::new(self.ids.clone(), self.unresolved_ctxt);
+        n.visit_with(&mut v);
+        for &span in &v.sources {
+            self.diagnostics.push(Diagnostic::error(
+                Reason::RuntimeReference,
+                span,
+                "dedent is imported at runtime, which is disallowed by the noRuntime option",
+            ));
+        }
     }
 }
 
 impl VisitMut for MainVisitor {
     fn visit_mut_module(&mut self, n: &mut Module) {
-        let imports = collect_imports(&self.ids, n);
-        if imports.is_empty() {
-            return;
-        }
+        let imports = collect_imports(&self.ids, self.unresolved_ctxt, n);
+        if !imports.is_empty() {
+            let removable_ids = self.transform(n, imports);
 
-        let mut v = TransformVisitor::new(self.ids.clone(), imports);
-        v.visit_mut_module(n);
+            // TODO: use drain_filter once stabilized
+            n.body = mem::take(&mut n.body)
+                .into_iter()
+                .flat_map(|item| {
+                    let mut item = Some(item);
+                    modify_import(&mut item, &removable_ids, self.unresolved_ctxt);
+                    item
+                })
+                .collect::<Vec<_>>();
+        }
+        self.report_runtime_imports(n);
+    }
 
-        let mut v = FindReferenceVisitor::new(v.removable_ids);
-        v.visit_module(n);
+    fn visit_mut_script(&mut self, n: &mut Script) {
+        let imports = collect_requires(&self.ids, self.unresolved_ctxt, n);
+        if !imports.is_empty() {
+            let removable_ids = self.transform(n, imports);
 
-        // TODO: use drain_filter once stabilized
-        n.body = mem::take(&mut n.body)
-            .into_iter()
-            .flat_map(|item| {
-                let mut item = Some(item);
-                modify_import(&mut item, &v.removable_ids);
-                item
-            })
-            .collect::<Vec<_>>();
+            // TODO: use drain_filter once stabilized
+            n.body = mem::take(&mut n.body)
+                .into_iter()
+                .flat_map(|stmt| {
+                    let mut stmt = Some(stmt);
+                    modify_require(&mut stmt, &removable_ids, self.unresolved_ctxt);
+                    stmt
+                })
+                .collect::<Vec<_>>();
+        }
+        self.report_runtime_imports(n);
     }
 }
 
 #[derive(Debug, Clone)]
 struct Ids {
-    dedent: JsWord,
-    qnighy_dedent: JsWord,
+    /// Exported names of the dedent function
+    dedent: HashSet<JsWord>,
+    /// Module specifiers exporting the dedent function
+    sources: HashSet<JsWord>,
 }
 
 impl Ids {
-    fn new() -> Self {
+    fn new(config: &Config) -> Self {
         Self {
-            dedent: JsWord::from("dedent"),
-            qnighy_dedent: JsWord::from("@qnighy/dedent"),
+            dedent: config
+                .function_names
+                .iter()
+                .map(|name| JsWord::from(name.as_str()))
+                .collect(),
+            sources: config
+                .import_sources
+                .iter()
+                .map(|source| JsWord::from(source.as_str()))
+                .collect(),
         }
     }
 }
 
 struct TransformVisitor {
     ids: Ids,
+    config: Config,
+    unresolved_ctxt: SyntaxContext,
     imports: Imports,
     removable_ids: HashSet<Id>,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl TransformVisitor {
-    fn new(ids: Ids, imports: Imports) -> Self {
+    fn new(ids: Ids, config: Config, unresolved_ctxt: SyntaxContext, imports: Imports) -> Self {
         Self {
             ids,
+            config,
+            unresolved_ctxt,
             imports,
             removable_ids: HashSet::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Is `e` the global `String.raw`?
+    fn is_string_raw(&self, e: &Expr) -> bool {
+        let Expr::Member(e) = unwrap_transparent(e) else {
+            return false;
+        };
+        let Expr::Ident(obj) = unwrap_transparent(&e.obj) else {
+            return false;
+        };
+        &*obj.sym == "String"
+            && obj.span.ctxt == self.unresolved_ctxt
+            && member_name(&e.prop)
+                .map(|name| &*name == "raw")
+                .unwrap_or(false)
+    }
+
+    /// Warns about the lines whose removed indentation disagrees with the other lines.
+    fn check_indents(&mut self, quasis: &[TplElement], quasis_orig: &[&str]) {
+        let inconsistent = find_inconsistent_indents(quasis_orig, &self.config.dedent_options());
+        for InconsistentIndent { index, line } in inconsistent {
+            let span = quasis[index].span;
+            self.diagnostics.push(Diagnostic::warning(
+                Reason::MixedIndent,
+                Span::new(
+                    span.lo + BytePos(line.start as u32),
+                    span.lo + BytePos(line.end as u32),
+                    span.ctxt,
+                ),
+                "indentation of this line does not match the other lines in dedent",
+            ));
+        }
+    }
+
+    fn is_dedent_call(&self, e: &Expr) -> bool {
+        let Expr::Call(e) = e.unwrap_parens() else {
+            return false;
+        };
+        let Callee::Expr(callee) = &e.callee else {
+            return false;
+        };
+        self.imports.detect_dedent_fn(&self.ids, callee).is_some()
+    }
+
+    /// Tells whether `e` is `dedent(String.raw)`.
+    fn is_dedent_string_raw(&self, e: &Expr) -> bool {
+        if !self.is_dedent_call(e) {
+            return false;
         }
+        let Expr::Call(e) = e.unwrap_parens() else {
+            return false;
+        };
+        matches!(
+            &e.args[..],
+            [ExprOrSpread { spread: None, expr }] if self.is_string_raw(expr)
+        )
     }
 }
 
 impl VisitMut for TransformVisitor {
     fn visit_mut_expr(&mut self, n: &mut Expr) {
+        // Checked before visit_mut_tagged_tpl unwraps the tag
+        let string_raw = matches!(n, Expr::TaggedTpl(ttpl) if self.is_dedent_string_raw(&ttpl.tag));
         n.visit_mut_children_with(self);
         let Expr::TaggedTpl(ttpl) = n else {
             return;
         };
+        if string_raw {
+            // dedent(String.raw)`...` -> "..." if the substitutions are constants
+            if let Some(value) = fold_string_raw(&ttpl.tpl, self.unresolved_ctxt) {
+                *n = Lit::Str(Str {
+                    span: ttpl.span,
+                    value: JsWord::from(value),
+                    raw: None,
+                })
+                .into();
+            }
+            return;
+        }
         let Some(dedent_id) = self.imports.detect_dedent_fn(&self.ids, &ttpl.tag) else {
+            // Wrapper calls are diagnosed in visit_mut_tagged_tpl
+            if !self.is_dedent_call(&ttpl.tag) {
+                if let Some(span) = self.imports.find_reference(&self.ids, &ttpl.tag) {
+                    self.diagnostics.push(Diagnostic::warning(
+                        Reason::UnsupportedTag,
+                        span,
+                        "dedent is left untransformed: unsupported form of the tag",
+                    ));
+                }
+            }
             return;
         };
         let tpl = &n.as_tagged_tpl().unwrap().tpl;
 
-        let quasis_orig = tpl
-            .quasis
-            .iter()
-            .map(|elem| elem.raw.to_owned())
-            .collect::<Vec<_>>();
-        let quasis = dedent_raw(&quasis_orig);
-        let Ok(cooked) = quasis.iter().map(|raw| cook(raw)).collect::<Result<Vec<_>, _>>() else {
+        let quasis_orig = tpl.quasis.iter().map(|elem| &*elem.raw).collect::<Vec<_>>();
+        self.check_indents(&tpl.quasis, &quasis_orig);
+        let (quasis, maps) = dedent_raw_mapped(&quasis_orig, &self.config.dedent_options());
+        let mut cooked = Vec::with_capacity(quasis.len());
+        let mut lines = Vec::new();
+        let mut first_error = None;
+        for (index, ((elem, quasi), map)) in tpl.quasis.iter().zip(&quasis).zip(&maps).enumerate() {
+            let (c, errors) = cook_best_effort(quasi, Grammar::Template);
+            for e in &errors {
+                self.diagnostics.push(Diagnostic::warning(
+                    Reason::InvalidEscape,
+                    original_span(elem, map, &e.range()),
+                    format!("dedent throws at runtime: {}", e),
+                ));
+            }
+            first_error = first_error.or(errors.into_iter().next().map(|e| (index, e)));
+            if self.config.output == OutputMode::Es5 {
+                if let Some(elem_lines) = cooked_lines(elem, quasi, map) {
+                    lines.push((elem.span, elem_lines));
+                }
+            }
+            cooked.push(c.into_string());
+        }
+        let quasis = changed_atoms(quasis);
+        if let Some((index, e)) = first_error {
+            // The runtime evaluates all the substitutions, stringifies the ones
+            // preceding the invalid quasi, and then throws a SyntaxError
+            let ttpl = n.take().tagged_tpl().unwrap();
+            *n = throw_syntax_error(
+                ttpl.span,
+                &e.to_string(),
+                ttpl.tpl.exprs,
+                index,
+                self.config.output,
+            );
+            self.removable_ids.insert(dedent_id);
             return;
-        };
+        }
         let ttpl = n.take().tagged_tpl().unwrap();
         let mut tpl = *ttpl.tpl;
-        for ((elem, new_quasi), cooked) in tpl.quasis.iter_mut().zip(&quasis).zip(&cooked) {
-            elem.raw = Atom::new(new_quasi.as_str());
+        for ((elem, new_quasi), cooked) in tpl.quasis.iter_mut().zip(quasis).zip(&cooked) {
+            if let Some(new_quasi) = new_quasi {
+                elem.raw = new_quasi;
+            }
             elem.cooked = cooked.as_ref().map(|cooked| Atom::new(cooked.as_str()));
         }
 
-        *n = Expr::Tpl(tpl);
+        fold_constants(
+            &mut tpl,
+            self.config.reindent_substitutions,
+            &self.config.dedent_options(),
+            self.unresolved_ctxt,
+        );
+        if self.config.normalize_escapes {
+            for elem in &mut tpl.quasis {
+                // The escapes are already known to be valid
+                elem.raw = Atom::new(normalize_raw(&elem.raw).unwrap());
+            }
+        }
+
+        *n = match self.config.output {
+            OutputMode::Template => Expr::Tpl(tpl),
+            OutputMode::String => tpl_to_str(tpl).unwrap_or_else(Expr::Tpl),
+            OutputMode::Es5 => tpl_to_str(tpl)
+                .or_else(|tpl| tpl_to_concat(tpl, &lines))
+                .unwrap_or_else(Expr::Tpl),
+        };
         self.removable_ids.insert(dedent_id);
     }
     fn visit_mut_tagged_tpl(&mut self, n: &mut TaggedTpl) {
@@ -136,6 +389,23 @@ impl VisitMut for TransformVisitor {
                 expr: _
             }]
         ) {
+            let diagnostic = if let Some(spread) = tag_orig.args.iter().find_map(|arg| arg.spread) {
+                Diagnostic::warning(
+                    Reason::WrapperSpread,
+                    spread,
+                    "dedent is left untransformed: spread arguments are not supported",
+                )
+            } else {
+                Diagnostic::warning(
+                    Reason::WrapperArity,
+                    tag_orig.span,
+                    format!(
+                        "dedent is left untransformed: expected exactly one argument, found {}",
+                        tag_orig.args.len()
+                    ),
+                )
+            };
+            self.diagnostics.push(diagnostic);
             return;
         }
 
@@ -147,15 +417,37 @@ impl VisitMut for TransformVisitor {
             .tpl
             .quasis
             .iter()
-            .map(|elem| elem.raw.to_owned())
+            .map(|elem| &*elem.raw)
             .collect::<Vec<_>>();
-        let quasis = dedent_raw(&quasis_orig);
-        for (elem, new_quasi) in n.tpl.quasis.iter_mut().zip(&quasis) {
-            elem.raw = Atom::new(new_quasi.as_str());
-            elem.cooked = cook(new_quasi.as_str()).unwrap_or(None).map(Atom::new);
+        self.check_indents(&n.tpl.quasis, &quasis_orig);
+        let quasis = changed_atoms(dedent_raw_with(&quasis_orig, &self.config.dedent_options()));
+        for (elem, new_quasi) in n.tpl.quasis.iter_mut().zip(quasis) {
+            if let Some(new_quasi) = new_quasi {
+                elem.raw = new_quasi;
+            }
+            elem.cooked = cook(&elem.raw).unwrap_or(None).map(Atom::new);
         }
         self.removable_ids.insert(dedent_id);
     }
+    fn visit_mut_call_expr(&mut self, n: &mut CallExpr) {
+        n.visit_mut_children_with(self);
+        let Callee::Expr(callee) = &n.callee else {
+            return;
+        };
+        if self.imports.detect_dedent_fn(&self.ids, callee).is_none() {
+            return;
+        }
+        let Some(ExprOrSpread { spread: None, expr: arg }) = n.args.first() else {
+            return;
+        };
+        if matches!(arg.unwrap_parens(), Expr::Lit(Lit::Str(_)) | Expr::Tpl(_)) {
+            self.diagnostics.push(Diagnostic::error(
+                Reason::StringArgument,
+                n.span,
+                "dedent called with a string throws at runtime: use dedent`...` instead of dedent(\"...\")",
+            ));
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -169,8 +461,34 @@ impl Imports {
         self.dedent.is_empty() && self.ns.is_empty()
     }
 
+    /// Returns the span of a dedent import which `e` may evaluate to or call.
+    fn find_reference(&self, ids: &Ids, e: &Expr) -> Option<Span> {
+        match unwrap_transparent(e) {
+            Expr::Ident(e) => self.dedent.contains(&e.to_id()).then_some(e.span),
+            Expr::Member(e) => {
+                if self.ns_member(ids, e).is_some() {
+                    Some(e.span)
+                } else {
+                    self.find_reference(ids, &e.obj)
+                }
+            }
+            Expr::Call(e) => match &e.callee {
+                Callee::Expr(callee) => self.find_reference(ids, callee),
+                _ => None,
+            },
+            Expr::Cond(e) => self
+                .find_reference(ids, &e.cons)
+                .or_else(|| self.find_reference(ids, &e.alt)),
+            Expr::Bin(e) if e.op.may_short_circuit() => self
+                .find_reference(ids, &e.left)
+                .or_else(|| self.find_reference(ids, &e.right)),
+            Expr::Seq(e) => self.find_reference(ids, e.exprs.last()?),
+            _ => None,
+        }
+    }
+
     fn detect_dedent_fn(&self, ids: &Ids, e: &Expr) -> Option<Id> {
-        let e = e.unwrap_parens();
+        let e = unwrap_transparent(e);
         match e {
             Expr::Ident(e) => {
                 let id = e.to_id();
@@ -178,43 +496,48 @@ impl Imports {
                     return Some(id);
                 }
             }
-            Expr::Member(e) => {
-                if let Some(obj) = e.obj.unwrap_parens().as_ident() {
-                    let id = obj.to_id();
-                    if self.ns.contains(&id)
-                        && member_name(&e.prop)
-                            .map(|name| *name == ids.dedent)
-                            .unwrap_or(false)
-                    {
-                        return Some(id);
-                    }
-                }
-            }
+            Expr::Member(e) => return self.ns_member(ids, e),
             _ => {}
         }
         None
     }
+
+    /// Returns the namespace of `ns.dedent`, if `e` is in this form.
+    fn ns_member(&self, ids: &Ids, e: &MemberExpr) -> Option<Id> {
+        let id = unwrap_transparent(&e.obj).as_ident()?.to_id();
+        (self.ns.contains(&id)
+            && member_name(&e.prop)
+                .map(|name| ids.dedent.contains(&name))
+                .unwrap_or(false))
+        .then_some(id)
+    }
 }
 
-fn collect_imports(ids: &Ids, module: &Module) -> Imports {
+fn collect_imports(ids: &Ids, unresolved_ctxt: SyntaxContext, module: &Module) -> Imports {
     let mut imports = Imports {
         dedent: HashSet::new(),
         ns: HashSet::new(),
     };
     for stmt in &module.body {
-        let Some(decl) = stmt.as_module_decl() else {
-            continue;
-        };
-        let Some(decl) = decl.as_import() else {
-            continue;
+        let decl = match stmt {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(decl)) => decl,
+            ModuleItem::ModuleDecl(ModuleDecl::TsImportEquals(decl)) => {
+                collect_import_equals(ids, decl, &mut imports);
+                continue;
+            }
+            ModuleItem::ModuleDecl(_) => continue,
+            ModuleItem::Stmt(stmt) => {
+                collect_require(ids, unresolved_ctxt, stmt, &mut imports);
+                continue;
+            }
         };
-        if decl.src.value != ids.qnighy_dedent {
+        if !ids.sources.contains(&decl.src.value) {
             continue;
         }
         for spec in &decl.specifiers {
             match spec {
                 ImportSpecifier::Named(spec) => {
-                    if *import_name(spec) == ids.dedent {
+                    if ids.dedent.contains(import_name(spec)) {
                         let local = spec.local.to_id();
                         imports.dedent.insert(local);
                     }
@@ -230,14 +553,98 @@ fn collect_imports(ids: &Ids, module: &Module) -> Imports {
     imports
 }
 
+/// Collects TypeScript import-equals declarations as namespace imports:
+///
+/// ```ts
+/// import m = require("@qnighy/dedent");
+/// ```
+fn collect_import_equals(ids: &Ids, decl: &TsImportEqualsDecl, imports: &mut Imports) {
+    if decl.is_export || decl.is_type_only {
+        return;
+    }
+    let Some(src) = import_equals_source(decl) else {
+        return;
+    };
+    if ids.sources.contains(src) {
+        imports.ns.insert(decl.id.to_id());
+    }
+}
+
+fn collect_requires(ids: &Ids, unresolved_ctxt: SyntaxContext, script: &Script) -> Imports {
+    let mut imports = Imports {
+        dedent: HashSet::new(),
+        ns: HashSet::new(),
+    };
+    for stmt in &script.body {
+        collect_require(ids, unresolved_ctxt, stmt, &mut imports);
+    }
+    imports
+}
+
+/// Collects top-level CommonJS bindings:
+///
+/// ```js
+/// const { dedent } = require("@qnighy/dedent");
+/// const m = require("@qnighy/dedent");
+/// ```
+fn collect_require(ids: &Ids, unresolved_ctxt: SyntaxContext, stmt: &Stmt, imports: &mut Imports) {
+    let Stmt::Decl(Decl::Var(decl)) = stmt else {
+        return;
+    };
+    for decl in &decl.decls {
+        let Some(src) = decl
+            .init
+            .as_deref()
+            .and_then(|init| require_source(init, unresolved_ctxt))
+        else {
+            continue;
+        };
+        if !ids.sources.contains(src) {
+            continue;
+        }
+        match &decl.name {
+            Pat::Ident(name) => {
+                imports.ns.insert(name.to_id());
+            }
+            Pat::Object(pat) => {
+                for prop in &pat.props {
+                    let Some((key, local)) = destructured_binding(prop) else {
+                        continue;
+                    };
+                    if ids.dedent.contains(key) {
+                        imports.dedent.insert(local.to_id());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 #[derive(Debug)]
 struct FindReferenceVisitor {
+    ids: Ids,
+    unresolved_ctxt: SyntaxContext,
+    imports: Imports,
     removable_ids: HashSet<Id>,
+    /// References to the dedent imports that survived the transformation
+    residual_references: Vec<Span>,
 }
 
 impl FindReferenceVisitor {
-    fn new(removable_ids: HashSet<Id>) -> Self {
-        Self { removable_ids }
+    fn new(
+        ids: Ids,
+        unresolved_ctxt: SyntaxContext,
+        imports: Imports,
+        removable_ids: HashSet<Id>,
+    ) -> Self {
+        Self {
+            ids,
+            unresolved_ctxt,
+            imports,
+            removable_ids,
+            residual_references: Vec::new(),
+        }
     }
 }
 
@@ -245,12 +652,118 @@ impl Visit for FindReferenceVisitor {
     fn visit_import_specifier(&mut self, _n: &ImportSpecifier) {
         // skip
     }
+    fn visit_ts_import_equals_decl(&mut self, n: &TsImportEqualsDecl) {
+        // skip the binding
+        n.module_ref.visit_with(self);
+    }
+    fn visit_var_declarator(&mut self, n: &VarDeclarator) {
+        let is_dedent_require = n
+            .init
+            .as_deref()
+            .and_then(|init| require_source(init, self.unresolved_ctxt))
+            .map(|src| self.ids.sources.contains(src))
+            .unwrap_or(false);
+        if is_dedent_require {
+            // skip the binding
+            n.init.visit_with(self);
+        } else {
+            n.visit_children_with(self);
+        }
+    }
+    fn visit_member_prop(&mut self, n: &MemberProp) {
+        // skip non-computed property names
+        if let MemberProp::Computed(n) = n {
+            n.visit_with(self);
+        }
+    }
+    fn visit_prop_name(&mut self, n: &PropName) {
+        // skip non-computed property names
+        if let PropName::Computed(n) = n {
+            n.visit_with(self);
+        }
+    }
     fn visit_ident(&mut self, n: &Ident) {
-        self.removable_ids.remove(&n.to_id());
+        let id = n.to_id();
+        if self.imports.dedent.contains(&id) || self.imports.ns.contains(&id) {
+            self.residual_references.push(n.span);
+        }
+        self.removable_ids.remove(&id);
+    }
+}
+
+/// Finds the imports, re-exports and requires of the dedent modules.
+#[derive(Debug)]
+struct RuntimeImportVisitor {
+    ids: Ids,
+    unresolved_ctxt: SyntaxContext,
+    /// Spans of the module specifiers
+    sources: Vec<Span>,
+}
+
+impl RuntimeImportVisitor {
+    fn new(ids: Ids, unresolved_ctxt: SyntaxContext) -> Self {
+        Self {
+            ids,
+            unresolved_ctxt,
+            sources: Vec::new(),
+        }
+    }
+
+    fn check_source(&mut self, src: &Str) {
+        if self.ids.sources.contains(&src.value) {
+            self.sources.push(src.span);
+        }
+    }
+}
+
+impl Visit for RuntimeImportVisitor {
+    fn visit_import_decl(&mut self, n: &ImportDecl) {
+        if !n.type_only {
+            self.check_source(&n.src);
+        }
+    }
+    fn visit_named_export(&mut self, n: &NamedExport) {
+        if let (false, Some(src)) = (n.type_only, &n.src) {
+            self.check_source(src);
+        }
+    }
+    fn visit_export_all(&mut self, n: &ExportAll) {
+        if !n.type_only {
+            self.check_source(&n.src);
+        }
+    }
+    fn visit_ts_import_equals_decl(&mut self, n: &TsImportEqualsDecl) {
+        if let (false, TsModuleRef::TsExternalModuleRef(module_ref)) =
+            (n.is_type_only, &n.module_ref)
+        {
+            self.check_source(&module_ref.expr);
+        }
+    }
+    fn visit_call_expr(&mut self, n: &CallExpr) {
+        if let Some(src) = require_call_source(n, self.unresolved_ctxt) {
+            self.check_source(src);
+        }
+        n.visit_children_with(self);
     }
 }
 
-fn modify_import(orig_item: &mut Option<ModuleItem>, removable_ids: &HashSet<Id>) {
+fn modify_import(
+    orig_item: &mut Option<ModuleItem>,
+    removable_ids: &HashSet<Id>,
+    unresolved_ctxt: SyntaxContext,
+) {
+    if let Some(ModuleItem::Stmt(stmt)) = orig_item {
+        let mut stmt = Some(stmt.take());
+        modify_require(&mut stmt, removable_ids, unresolved_ctxt);
+        *orig_item = stmt.map(ModuleItem::Stmt);
+        return;
+    }
+    if let Some(ModuleItem::ModuleDecl(ModuleDecl::TsImportEquals(decl))) = orig_item {
+        if import_equals_source(decl).is_some() && removable_ids.contains(&decl.id.to_id()) {
+            *orig_item = None;
+        }
+        return;
+    }
     let Some(ModuleItem::ModuleDecl(ModuleDecl::Import(item))) = orig_item else {
         return;
     };
@@ -277,6 +790,71 @@ fn modify_import(orig_item: &mut Option<ModuleItem>, removable_ids: &HashSet<Id>
     }
 }
 
+fn modify_require(
+    orig_stmt: &mut Option<Stmt>,
+    removable_ids: &HashSet<Id>,
+    unresolved_ctxt: SyntaxContext,
+) {
+    let Some(Stmt::Decl(Decl::Var(decl))) = orig_stmt else {
+        return;
+    };
+    let found = decl.decls.iter().any(|decl| match &decl.name {
+        Pat::Ident(name) => removable_ids.contains(&name.to_id()),
+        Pat::Object(pat) => pat.props.iter().any(|prop| {
+            destructured_binding(prop)
+                .map(|(_, local)| removable_ids.contains(&local.to_id()))
+                .unwrap_or(false)
+        }),
+        _ => false,
+    });
+    if !found {
+        return;
+    }
+    // TODO: use drain_filter once stabilized
+    decl.decls = mem::take(&mut decl.decls)
+        .into_iter()
+        .filter_map(|mut decl| {
+            if decl
+                .init
+                .as_deref()
+                .and_then(|init| require_source(init, unresolved_ctxt))
+                .is_none()
+            {
+                return Some(decl);
+            }
+            match &mut decl.name {
+                Pat::Ident(name) => {
+                    if removable_ids.contains(&name.to_id()) {
+                        return None;
+                    }
+                }
+                Pat::Object(pat) => {
+                    if pat.props.iter().any(|prop| prop.is_rest()) {
+                        // Removing a property would change what the rest element receives
+                        return Some(decl);
+                    }
+                    pat.props = mem::take(&mut pat.props)
+                        .into_iter()
+                        .filter(|prop| {
+                            !destructured_binding(prop)
+                                .map(|(_, local)| removable_ids.contains(&local.to_id()))
+                                .unwrap_or(false)
+                        })
+                        .collect::<Vec<_>>();
+                    if pat.props.is_empty() {
+                        return None;
+                    }
+                }
+                _ => {}
+            }
+            Some(decl)
+        })
+        .collect::<Vec<_>>();
+    if decl.decls.is_empty() {
+        *orig_stmt = None;
+    }
+}
+
 fn as_value(expr: Box<Expr>) -> Box<Expr> {
     let inner = expr.unwrap_parens();
     if inner.is_member()
@@ -308,72 +886,409 @@ fn as_value(expr: Box<Expr>) -> Box<Expr> {
     }
 }
 
-fn member_name(prop: &MemberProp) -> Option<&JsWord> {
-    match prop {
-        MemberProp::Ident(prop) => Some(&prop.sym),
-        MemberProp::Computed(prop) => match prop.expr.unwrap_parens() {
-            Expr::Lit(Lit::Str(prop)) => Some(&prop.value),
-            _ => None,
-        },
-        _ => None,
-    }
+/// Converts a substitution-free template into a string literal.
+fn tpl_to_str(tpl: Tpl) -> Result<Expr, Tpl> {
+    let [elem] = &tpl.quasis[..] else {
+        return Err(tpl);
+    };
+    let Some(cooked) = &elem.cooked else {
+        return Err(tpl);
+    };
+    Ok(Lit::Str(Str {
+        span: tpl.span,
+        value: JsWord::from(&**cooked),
+        raw: None,
+    })
+    .into())
 }
 
-fn import_name(spec: &ImportNamedSpecifier) -> &JsWord {
-    if let Some(imported) = &spec.imported {
-        match &imported {
-            ModuleExportName::Ident(imported) => &imported.sym,
-            ModuleExportName::Str(imported) => &imported.value,
+/// Converts a template into `"...".concat(...)`, which stringifies the substitutions
+/// in the same way and order as template literals do.
+///
+/// The elements found in `lines`, keyed by their spans, are emitted line by line.
+fn tpl_to_concat(tpl: Tpl, lines: &[(Span, Vec<Str>)]) -> Result<Expr, Tpl> {
+    if tpl.quasis.iter().any(|elem| elem.cooked.is_none()) {
+        return Err(tpl);
+    }
+    let mut quasis = tpl.quasis.into_iter().map(|elem| {
+        // Elements merged with folded substitutions span more than any of the lines
+        match lines.iter().find(|(span, _)| *span == elem.span) {
+            Some((_, lines)) => lines.clone(),
+            None => vec![Str {
+                span: elem.span,
+                value: JsWord::from(&*elem.cooked.unwrap()),
+                raw: None,
+            }],
         }
-    } else {
-        &spec.local.sym
+    });
+    let str_arg = |s: Str| ExprOrSpread {
+        spread: None,
+        expr: Box::new(Lit::Str(s).into()),
+    };
+    let concat = |obj: Expr, args: Vec<ExprOrSpread>| -> Expr {
+        CallExpr {
+            span: DUMMY_SP,
+            callee: Callee::Expr(Box::new(
+                MemberExpr {
+                    span: DUMMY_SP,
+                    obj: Box::new(obj),
+                    prop: MemberProp::Ident(Ident::new(JsWord::from("concat"), DUMMY_SP)),
+                }
+                .into(),
+            )),
+            args,
+            type_args: None,
+        }
+        .into()
+    };
+    let mut first = quasis.next().unwrap().into_iter();
+    let mut result = Expr::from(Lit::Str(first.next().unwrap()));
+    let mut args = first.map(str_arg).collect::<Vec<_>>();
+    // Concatenate one substitution at a time so that each ToString happens
+    // before the next substitution is evaluated: "a".concat(x, "b").concat(y)
+    for (expr, quasi) in tpl.exprs.into_iter().zip(quasis) {
+        args.push(ExprOrSpread { spread: None, expr });
+        args.extend(
+            quasi
+                .into_iter()
+                .filter(|s| !s.value.is_empty())
+                .map(str_arg),
+        );
+        result = concat(result, mem::take(&mut args));
     }
+    if !args.is_empty() {
+        result = concat(result, args);
+    }
+    Ok(result)
 }
 
-fn local_name(spec: &ImportSpecifier) -> &Ident {
-    match spec {
-        ImportSpecifier::Default(spec) => &spec.local,
-        ImportSpecifier::Named(spec) => &spec.local,
-        ImportSpecifier::Namespace(spec) => &spec.local,
+/// Splits a multi-line element into a string literal per line, each spanning the line
+/// in the original source so that source maps can point inside the element.
+///
+/// `raw` is the dedented raw text of `elem`. Lines cooked to empty strings are omitted.
+fn cooked_lines(elem: &TplElement, raw: &str, map: &OffsetMap) -> Option<Vec<Str>> {
+    if !raw.contains('\n') {
+        return None;
     }
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for line in raw.split_inclusive('\n') {
+        let end = start + line.len();
+        // Escape sequences never span lines, except for line continuations which
+        // end with the line terminator
+        let cooked = cook(line).ok()??;
+        if !cooked.is_empty() {
+            lines.push(Str {
+                span: original_span(elem, map, &(start..end)),
+                value: JsWord::from(cooked),
+                raw: None,
+            });
+        }
+        start = end;
+    }
+    (!lines.is_empty()).then_some(lines)
 }
 
-#[cfg(test)]
-mod test_basic_behavior {
-    use super::*;
-    use swc_core::ecma::transforms::testing::test;
-
-    test!(
-        Default::default(),
-        |_| as_folder(MainVisitor::new()),
-        transform_dedent_calls,
-        r#"import { dedent } from "@qnighy/dedent";
-const text = dedent`\
-  foo
-  bar
-`;
-"#,
-        r#"const text = `\
-foo
-bar
-`;"#
-    );
-
-    test!(
-        Default::default(),
-        |_| as_folder(MainVisitor::new()),
-        transform_wrapping_dedent_calls,
-        r#"import { dedent } from "@qnighy/dedent";
-const text = dedent(foo)`\
-  foo
-  bar
-`;
-"#,
-        r#"const text = foo`\
-foo
-bar
-`;"#
-    );
+/// Wraps `expr` to convert it to a string as template literals do: `` `${expr}` ``,
+/// or `"".concat(expr)` for ES5.
+fn stringify(expr: Box<Expr>, output: OutputMode) -> Box<Expr> {
+    let empty = || TplElement {
+        span: DUMMY_SP,
+        tail: false,
+        cooked: Some(Atom::from("")),
+        raw: Atom::from(""),
+    };
+    let tpl = Tpl {
+        span: DUMMY_SP,
+        exprs: vec![expr],
+        quasis: vec![
+            empty(),
+            TplElement {
+                tail: true,
+                ..empty()
+            },
+        ],
+    };
+    Box::new(match output {
+        OutputMode::Template | OutputMode::String => Expr::Tpl(tpl),
+        OutputMode::Es5 => tpl_to_concat(tpl, &[]).unwrap_or_else(Expr::Tpl),
+    })
+}
+
+/// Converts the dedented raw texts into atoms, leaving `None` for the unchanged ones.
+fn changed_atoms(quasis: Vec<Cow<'_, str>>) -> Vec<Option<Atom>> {
+    quasis
+        .into_iter()
+        .map(|quasi| match quasi {
+            Cow::Borrowed(_) => None,
+            Cow::Owned(quasi) => Some(Atom::new(quasi)),
+        })
+        .collect()
+}
+
+/// Computes the original span of a range in the dedented raw text of `elem`.
+fn original_span(elem: &TplElement, map: &OffsetMap, range: &Range<usize>) -> Span {
+    let to_pos = |offset: usize| (elem.span.lo + BytePos(offset as u32)).min(elem.span.hi);
+    Span::new(
+        to_pos(map.to_original(range.start)),
+        to_pos(map.to_original_end(range.end)),
+        elem.span.ctxt,
+    )
+}
+
+/// Builds an expression that evaluates `args` in order, stringifies the first `stringified`
+/// of them, and then throws a `SyntaxError`:
+///
+/// ```js
+/// (function () { `${arguments[0]}`; throw new SyntaxError("..."); })(args...)
+/// ```
+fn throw_syntax_error(
+    span: Span,
+    message: &str,
+    args: impl IntoIterator<Item = Box<Expr>>,
+    stringified: usize,
+    output: OutputMode,
+) -> Expr {
+    let mut stmts = (0..stringified)
+        .map(|i| {
+            let arg = MemberExpr {
+                span: DUMMY_SP,
+                obj: Box::new(Ident::new(JsWord::from("arguments"), DUMMY_SP).into()),
+                prop: MemberProp::Computed(ComputedPropName {
+                    span: DUMMY_SP,
+                    expr: Box::new(Lit::Num(Number::from(i as f64)).into()),
+                }),
+            };
+            Stmt::Expr(ExprStmt {
+                span: DUMMY_SP,
+                expr: stringify(Box::new(arg.into()), output),
+            })
+        })
+        .collect::<Vec<_>>();
+    let error = NewExpr {
+        span: DUMMY_SP,
+        callee: Box::new(Ident::new(JsWord::from("SyntaxError"), DUMMY_SP).into()),
+        args: Some(vec![ExprOrSpread {
+            spread: None,
+            expr: Box::new(Lit::Str(Str::from(message)).into()),
+        }]),
+        type_args: None,
+    };
+    stmts.push(Stmt::Throw(ThrowStmt {
+        span: DUMMY_SP,
+        arg: Box::new(error.into()),
+    }));
+    let func = FnExpr {
+        ident: None,
+        function: Box::new(Function {
+            params: vec![],
+            decorators: vec![],
+            span: DUMMY_SP,
+            body: Some(BlockStmt {
+                span: DUMMY_SP,
+                stmts,
+            }),
+            is_generator: false,
+            is_async: false,
+            type_params: None,
+            return_type: None,
+        }),
+    };
+    CallExpr {
+        span,
+        callee: Callee::Expr(Box::new(func.into())),
+        args: args
+            .into_iter()
+            .map(|expr| ExprOrSpread { spread: None, expr })
+            .collect(),
+        type_args: None,
+    }
+    .into()
+}
+
+/// Unwraps expressions that evaluate to the inner expression as is:
+///
+/// - Parentheses: `(expr)`
+/// - TypeScript wrappers: `expr as T`, `expr satisfies T`, `expr!`, `<T>expr`
+/// - Sequences led by literals, as emitted by the module interop: `(0, expr)`
+fn unwrap_transparent(e: &Expr) -> &Expr {
+    let mut cur = e;
+    loop {
+        cur = match cur {
+            Expr::Paren(e) => &e.expr,
+            Expr::TsAs(e) => &e.expr,
+            Expr::TsSatisfies(e) => &e.expr,
+            Expr::TsNonNull(e) => &e.expr,
+            Expr::TsTypeAssertion(e) => &e.expr,
+            Expr::Seq(e) => match e.exprs.split_last() {
+                Some((last, init)) if init.iter().all(|e| e.unwrap_parens().is_lit()) => last,
+                _ => return cur,
+            },
+            _ => return cur,
+        };
+    }
+}
+
+fn member_name(prop: &MemberProp) -> Option<JsWord> {
+    match prop {
+        MemberProp::Ident(prop) => Some(prop.sym.clone()),
+        MemberProp::Computed(prop) => match prop.expr.unwrap_parens() {
+            Expr::Lit(Lit::Str(prop)) => Some(prop.value.clone()),
+            // m[`dedent`]
+            Expr::Tpl(prop) if prop.exprs.is_empty() => {
+                let cooked = prop.quasis.first()?.cooked.as_ref()?;
+                Some(JsWord::from(&**cooked))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the specifier of `require("...")`, where `require` is the global one.
+fn require_source(e: &Expr, unresolved_ctxt: SyntaxContext) -> Option<&JsWord> {
+    let Expr::Call(call) = e.unwrap_parens() else {
+        return None;
+    };
+    require_call_source(call, unresolved_ctxt).map(|src| &src.value)
+}
+
+/// Returns the specifier literal of `require("...")`, where `require` is the global one.
+fn require_call_source(call: &CallExpr, unresolved_ctxt: SyntaxContext) -> Option<&Str> {
+    let Callee::Expr(callee) = &call.callee else {
+        return None;
+    };
+    if !callee
+        .as_ident()
+        .map(|callee| &*callee.sym == "require" && callee.span.ctxt == unresolved_ctxt)
+        .unwrap_or(false)
+    {
+        return None;
+    }
+    match &call.args[..] {
+        [ExprOrSpread { spread: None, expr }] => match &**expr {
+            Expr::Lit(Lit::Str(src)) => Some(src),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the specifier of `import m = require("...")`.
+fn import_equals_source(decl: &TsImportEqualsDecl) -> Option<&JsWord> {
+    match &decl.module_ref {
+        TsModuleRef::TsExternalModuleRef(module_ref) => Some(&module_ref.expr.value),
+        TsModuleRef::TsEntityName(_) => None,
+    }
+}
+
+/// Returns the property name and the local binding of a simple destructuring property.
+fn destructured_binding(prop: &ObjectPatProp) -> Option<(&JsWord, &Ident)> {
+    match prop {
+        // const { dedent } = ...
+        ObjectPatProp::Assign(prop) if prop.value.is_none() => Some((&prop.key.sym, &prop.key)),
+        // const { dedent: m } = ...
+        ObjectPatProp::KeyValue(prop) => {
+            let key = match &prop.key {
+                PropName::Ident(key) => &key.sym,
+                PropName::Str(key) => &key.value,
+                _ => return None,
+            };
+            let local = prop.value.as_ident()?;
+            Some((key, &local.id))
+        }
+        _ => None,
+    }
+}
+
+fn import_name(spec: &ImportNamedSpecifier) -> &JsWord {
+    if let Some(imported) = &spec.imported {
+        match &imported {
+            ModuleExportName::Ident(imported) => &imported.sym,
+            ModuleExportName::Str(imported) => &imported.value,
+        }
+    } else {
+        &spec.local.sym
+    }
+}
+
+fn local_name(spec: &ImportSpecifier) -> &Ident {
+    match spec {
+        ImportSpecifier::Default(spec) => &spec.local,
+        ImportSpecifier::Named(spec) => &spec.local,
+        ImportSpecifier::Namespace(spec) => &spec.local,
+    }
+}
+
+#[cfg(test)]
+mod test_utils {
+    use super::*;
+    use swc_core::common::chain;
+    use swc_core::ecma::parser::{Syntax, TsConfig};
+    use swc_core::ecma::transforms::base::resolver;
+    use swc_core::ecma::visit::Fold;
+
+    pub(crate) fn ts() -> Syntax {
+        Syntax::Typescript(TsConfig::default())
+    }
+
+    /// Runs the resolver before the transformation, so that globals can be told apart.
+    pub(crate) fn with_resolver() -> impl Fold {
+        let unresolved_mark = Mark::new();
+        chain!(
+            resolver(unresolved_mark, Mark::new(), false),
+            as_folder(MainVisitor::new().with_unresolved_mark(unresolved_mark))
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_basic_behavior {
+    use super::*;
+    use swc_core::ecma::transforms::testing::test;
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        transform_dedent_calls,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent`\
+  foo
+  bar
+`;
+"#,
+        r#"const text = `\
+foo
+bar
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        transform_wrapping_dedent_calls,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent(foo)`\
+  foo
+  bar
+`;
+"#,
+        r#"const text = foo`\
+foo
+bar
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        transform_crlf_source,
+        "import { dedent } from \"@qnighy/dedent\";\r\nconst text = dedent`\\\r\n  foo\r\n  bar\r\n`;\r\n",
+        r#"const text = `\
+foo
+bar
+`;"#
+    );
 }
 
 #[cfg(test)]
@@ -565,18 +1480,54 @@ mod test_escape_handling {
     test!(
         Default::default(),
         |_| as_folder(MainVisitor::new()),
-        skip_transformation_if_there_is_an_invalid_escape_in_the_direct_form,
+        throw_if_there_is_an_invalid_escape_in_the_direct_form,
         r#"import { dedent } from "@qnighy/dedent";
 const text = dedent`\
   foo
   bar\9
 `;
 "#,
+        r#"const text = function() {
+    throw new SyntaxError("\\8 and \\9 are not allowed in template strings.");
+}();"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        evaluate_substitutions_before_throwing,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent`\
+  ${foo()}
+  \u{110000}${bar}
+`;
+"#,
+        r#"const text = function() {
+    `${arguments[0]}`;
+    throw new SyntaxError("Undefined Unicode code-point");
+}(foo(), bar);"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        stringify_substitutions_before_throwing,
         r#"import { dedent } from "@qnighy/dedent";
 const text = dedent`\
-  foo
-  bar\9
-`;"#
+  ${Symbol()}${{ toString() { log(); return ""; } }}
+  \u{110000}${Symbol()}
+`;
+"#,
+        r#"const text = function() {
+    `${arguments[0]}`;
+    `${arguments[1]}`;
+    throw new SyntaxError("Undefined Unicode code-point");
+}(Symbol(), {
+    toString () {
+        log();
+        return "";
+    }
+}, Symbol());"#
     );
 
     test!(
@@ -813,3 +1764,1476 @@ bar
 m.dedent;"#
     );
 }
+
+#[cfg(test)]
+mod test_config {
+    use super::*;
+    use swc_core::ecma::transforms::testing::test;
+
+    fn acme_config() -> Config {
+        Config {
+            import_sources: vec!["@qnighy/dedent".to_owned(), "@acme/text".to_owned()],
+            function_names: vec!["dedent".to_owned(), "outdent".to_owned()],
+            ..Default::default()
+        }
+    }
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::with_config(&acme_config())),
+        detect_custom_import_sources,
+        r#"import { dedent } from "@acme/text";
+const text = dedent`\
+  foo
+  bar
+`;
+"#,
+        r#"const text = `\
+foo
+bar
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::with_config(&acme_config())),
+        detect_custom_function_names,
+        r#"import { outdent } from "@acme/text";
+import * as m from "@qnighy/dedent";
+const text1 = outdent`\
+  foo
+  bar
+`;
+const text2 = m.outdent`\
+  foo
+  bar
+`;
+"#,
+        r#"const text1 = `\
+foo
+bar
+`;
+const text2 = `\
+foo
+bar
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::with_config(&Config {
+            import_sources: vec!["@acme/text".to_owned()],
+            function_names: vec!["outdent".to_owned()],
+            ..Default::default()
+        })),
+        ignore_defaults_not_in_config,
+        r#"import { dedent } from "@qnighy/dedent";
+import { dedent as d } from "@acme/text";
+const text1 = dedent`\
+  foo
+`;
+const text2 = d`\
+  foo
+`;
+"#,
+        r#"import { dedent } from "@qnighy/dedent";
+import { dedent as d } from "@acme/text";
+const text1 = dedent`\
+  foo
+`;
+const text2 = d`\
+  foo
+`;"#
+    );
+}
+
+#[cfg(test)]
+mod test_commonjs {
+    use super::*;
+    use crate::test_utils::with_resolver;
+    use swc_core::ecma::transforms::testing::{test, Tester};
+
+    fn test_script(input: &str, expected: &str) {
+        Tester::run(|tester| {
+            let mut print_script = |name: &str, src: &str, transform: bool| {
+                let mut script =
+                    tester.with_parser(name, Default::default(), src, |p| p.parse_script())?;
+                if transform {
+                    script = script.fold_with(&mut as_folder(MainVisitor::new()));
+                }
+                let module = Module {
+                    span: script.span,
+                    body: script.body.into_iter().map(ModuleItem::Stmt).collect(),
+                    shebang: None,
+                };
+                Ok(tester.print(&module, &Default::default()))
+            };
+            let actual = print_script("input.js", input, true)?;
+            let expected = print_script("output.js", expected, false)?;
+            assert_eq!(actual, expected);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn transform_destructured_require_in_scripts() {
+        test_script(
+            r#"const { dedent } = require("@qnighy/dedent");
+const text = dedent`\
+  foo
+  bar
+`;
+"#,
+            r#"const text = `\
+foo
+bar
+`;
+"#,
+        );
+    }
+
+    #[test]
+    fn transform_namespace_require_in_scripts() {
+        test_script(
+            r#"const d = require("@qnighy/dedent");
+const text = d.dedent`\
+  foo
+  bar
+`;
+"#,
+            r#"const text = `\
+foo
+bar
+`;
+"#,
+        );
+    }
+
+    #[test]
+    fn keep_require_in_scripts_if_there_are_other_non_removable_uses() {
+        test_script(
+            r#"const { dedent: d, dedentRaw } = require("@qnighy/dedent");
+const text = d`\
+  foo
+  bar
+`;
+d;
+"#,
+            r#"const { dedent: d, dedentRaw } = require("@qnighy/dedent");
+const text = `\
+foo
+bar
+`;
+d;
+"#,
+        );
+    }
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        transform_require_in_modules,
+        r#"const { dedent: d } = require("@qnighy/dedent");
+const m = require("@qnighy/dedent");
+const text1 = d`\
+  foo
+  bar
+`;
+const text2 = m.dedent`\
+  foo
+  bar
+`;
+"#,
+        r#"const text1 = `\
+foo
+bar
+`;
+const text2 = `\
+foo
+bar
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        remove_only_the_destructured_property_if_other_properties_are_in_use,
+        r#"const { dedent, dedentRaw } = require("@qnighy/dedent"), fs = require("fs");
+const text = dedent`\
+  foo
+  bar
+`;
+"#,
+        r#"const { dedentRaw } = require("@qnighy/dedent"), fs = require("fs");
+const text = `\
+foo
+bar
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        keep_destructured_property_if_there_is_a_rest_element,
+        r#"const { dedent, ...rest } = require("@qnighy/dedent");
+const text = dedent`\
+  foo
+  bar
+`;
+"#,
+        r#"const { dedent, ...rest } = require("@qnighy/dedent");
+const text = `\
+foo
+bar
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        ignore_non_top_level_require,
+        r#"function f() {
+  const { dedent } = require("@qnighy/dedent");
+  return dedent`\
+    foo
+  `;
+}
+"#,
+        r#"function f() {
+  const { dedent } = require("@qnighy/dedent");
+  return dedent`\
+    foo
+  `;
+}"#
+    );
+
+    test!(
+        Default::default(),
+        |_| with_resolver(),
+        transform_global_require_with_resolver,
+        r#"const { dedent } = require("@qnighy/dedent");
+const text = dedent`\
+  foo
+`;
+"#,
+        r#"const text = `\
+foo
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| with_resolver(),
+        ignore_shadowed_require,
+        r#"const require = myLoader;
+const { dedent } = require("@qnighy/dedent");
+const text = dedent`\
+  foo
+`;
+"#,
+        r#"const require = myLoader;
+const { dedent } = require("@qnighy/dedent");
+const text = dedent`\
+  foo
+`;"#
+    );
+}
+
+#[cfg(test)]
+mod test_transparent_wrappers {
+    use super::*;
+    use crate::test_utils::ts;
+    use swc_core::ecma::transforms::testing::test;
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        detect_interop_sequence_expressions,
+        r#"const _dedent = require("@qnighy/dedent");
+const text1 = (0, _dedent.dedent)`\
+  foo
+  bar
+`;
+const text2 = (0, _dedent.dedent)(foo)`\
+  foo
+  bar
+`;
+"#,
+        r#"const text1 = `\
+foo
+bar
+`;
+const text2 = foo`\
+foo
+bar
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        ignore_sequence_expressions_with_side_effects,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = (foo(), dedent)`\
+  foo
+  bar
+`;
+"#,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = (foo(), dedent)`\
+  foo
+  bar
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        detect_namespace_imports_with_template_literal_computed_member_access,
+        r#"import * as m from "@qnighy/dedent";
+const text = m[`dedent`]`\
+  foo
+  bar
+`;
+"#,
+        r#"const text = `\
+foo
+bar
+`;"#
+    );
+
+    test!(
+        ts(),
+        |_| as_folder(MainVisitor::new()),
+        detect_typescript_wrappers,
+        r#"import { dedent } from "@qnighy/dedent";
+const text1 = (dedent as Tag)`\
+  foo
+`;
+const text2 = dedent!`\
+  foo
+`;
+const text3 = (dedent satisfies Tag)`\
+  foo
+`;
+const text4 = (<Tag>dedent)`\
+  foo
+`;
+const text5 = (dedent as any)(foo)`\
+  foo
+`;
+"#,
+        r#"const text1 = `\
+foo
+`;
+const text2 = `\
+foo
+`;
+const text3 = `\
+foo
+`;
+const text4 = `\
+foo
+`;
+const text5 = foo`\
+foo
+`;"#
+    );
+
+    test!(
+        ts(),
+        |_| as_folder(MainVisitor::new()),
+        detect_typescript_wrapped_namespaces,
+        r#"import * as m from "@qnighy/dedent";
+const text = (m as any)!.dedent`\
+  foo
+`;
+"#,
+        r#"const text = `\
+foo
+`;"#
+    );
+}
+
+#[cfg(test)]
+mod test_import_equals {
+    use super::*;
+    use crate::test_utils::ts;
+    use swc_core::ecma::transforms::testing::test;
+
+    test!(
+        ts(),
+        |_| as_folder(MainVisitor::new()),
+        transform_and_remove_import_equals,
+        r#"import d = require("@qnighy/dedent");
+const text = d.dedent`\
+  foo
+  bar
+`;
+"#,
+        r#"const text = `\
+foo
+bar
+`;"#
+    );
+
+    test!(
+        ts(),
+        |_| as_folder(MainVisitor::new()),
+        keep_import_equals_if_there_are_other_non_removable_uses,
+        r#"import d = require("@qnighy/dedent");
+const text = d.dedent`\
+  foo
+  bar
+`;
+d.dedentRaw;
+"#,
+        r#"import d = require("@qnighy/dedent");
+const text = `\
+foo
+bar
+`;
+d.dedentRaw;"#
+    );
+
+    test!(
+        ts(),
+        |_| as_folder(MainVisitor::new()),
+        ignore_exported_import_equals,
+        r#"export import d = require("@qnighy/dedent");
+const text = d.dedent`\
+  foo
+`;
+"#,
+        r#"export import d = require("@qnighy/dedent");
+const text = d.dedent`\
+  foo
+`;"#
+    );
+}
+
+#[cfg(test)]
+mod test_diagnostics {
+    use super::*;
+    use crate::diagnostics::Severity;
+    use swc_core::common::SourceMapper;
+    use swc_core::ecma::transforms::testing::Tester;
+
+    fn diagnose(src: &str) -> Vec<(Severity, &'static str, String)> {
+        diagnose_with_config(&Config::default(), src)
+    }
+
+    fn diagnose_with_config(config: &Config, src: &str) -> Vec<(Severity, &'static str, String)> {
+        Tester::run(|tester| {
+            let mut module = tester.parse_module("input.js", src)?;
+            let mut v = MainVisitor::with_config(config);
+            module.visit_mut_with(&mut v);
+            Ok(v.diagnostics
+                .iter()
+                .map(|d| {
+                    let snippet = tester.cm.span_to_snippet(d.span).unwrap();
+                    (d.severity, d.reason.code(), snippet)
+                })
+                .collect())
+        })
+    }
+
+    #[test]
+    fn no_diagnostics_for_transformed_code() {
+        assert_eq!(
+            diagnose(
+                r#"import { dedent } from "@qnighy/dedent";
+dedent`\
+  foo
+`;
+dedent(foo)`\
+  foo
+`;
+"#
+            ),
+            []
+        );
+    }
+
+    #[test]
+    fn warn_invalid_escape_in_the_direct_form() {
+        assert_eq!(
+            diagnose(
+                r#"import { dedent } from "@qnighy/dedent";
+dedent`\
+  foo${x}
+  bar\9
+`;
+"#
+            ),
+            [(Severity::Warning, "dedent/invalid-escape", "\\9".to_owned())]
+        );
+    }
+
+    #[test]
+    fn point_at_the_invalid_escape() {
+        assert_eq!(
+            diagnose(
+                r#"import { dedent } from "@qnighy/dedent";
+dedent`\
+    foo
+      bar \u{ABCDEF} baz
+`;
+"#
+            ),
+            [(
+                Severity::Warning,
+                "dedent/invalid-escape",
+                "\\u{ABCDEF}".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn point_at_the_invalid_escape_after_filled_tabs() {
+        assert_eq!(
+            diagnose_with_config(
+                &Config {
+                    tab_width: std::num::NonZeroUsize::new(4),
+                    ..Default::default()
+                },
+                "import { dedent } from \"@qnighy/dedent\";\ndedent`\\\n  foo\n\t\\x \\9\n`;\n"
+            ),
+            [
+                (
+                    Severity::Warning,
+                    "dedent/mixed-indent",
+                    "\t\\x \\9".to_owned()
+                ),
+                (Severity::Warning, "dedent/invalid-escape", "\\x".to_owned()),
+                (Severity::Warning, "dedent/invalid-escape", "\\9".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn point_at_the_invalid_escape_before_crlf() {
+        assert_eq!(
+            diagnose(
+                "import { dedent } from \"@qnighy/dedent\";\r\ndedent`\\\r\n  foo\r\n  bar \\9\r\n`;\r\n"
+            ),
+            [(Severity::Warning, "dedent/invalid-escape", "\\9".to_owned())]
+        );
+    }
+
+    #[test]
+    fn warn_every_invalid_escape() {
+        assert_eq!(
+            diagnose(
+                r#"import { dedent } from "@qnighy/dedent";
+dedent`\
+  foo\9 \x
+  ${x}\u{A
+`;
+"#
+            ),
+            [
+                (Severity::Warning, "dedent/invalid-escape", "\\9".to_owned()),
+                (Severity::Warning, "dedent/invalid-escape", "\\x".to_owned()),
+                (
+                    Severity::Warning,
+                    "dedent/invalid-escape",
+                    "\\u{A".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn warn_mixed_indentation() {
+        assert_eq!(
+            diagnose(
+                "import { dedent } from \"@qnighy/dedent\";\n\
+                 dedent`\\\n  foo\n\t\tbar\n  baz\n`;\n\
+                 dedent(tag)`\\\n\tfoo ${x}\n    bar\n`;\n"
+            ),
+            [
+                (
+                    Severity::Warning,
+                    "dedent/mixed-indent",
+                    "\t\tbar".to_owned()
+                ),
+                (
+                    Severity::Warning,
+                    "dedent/mixed-indent",
+                    "    bar".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn warn_wrapper_arity() {
+        assert_eq!(
+            diagnose(
+                r#"import { dedent } from "@qnighy/dedent";
+dedent()`foo`;
+dedent(foo, bar)`foo`;
+"#
+            ),
+            [
+                (
+                    Severity::Warning,
+                    "dedent/wrapper-arity",
+                    "dedent()".to_owned()
+                ),
+                (
+                    Severity::Warning,
+                    "dedent/wrapper-arity",
+                    "dedent(foo, bar)".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn warn_wrapper_spread() {
+        assert_eq!(
+            diagnose(
+                r#"import { dedent } from "@qnighy/dedent";
+dedent(...foo)`foo`;
+"#
+            ),
+            [(Severity::Warning, "dedent/wrapper-spread", "...".to_owned())]
+        );
+    }
+
+    #[test]
+    fn error_string_argument() {
+        assert_eq!(
+            diagnose(
+                r#"import { dedent } from "@qnighy/dedent";
+const text1 = dedent("foo");
+const text2 = dedent(`\
+  foo
+`);
+"#
+            ),
+            [
+                (
+                    Severity::Error,
+                    "dedent/string-argument",
+                    r#"dedent("foo")"#.to_owned()
+                ),
+                (
+                    Severity::Error,
+                    "dedent/string-argument",
+                    "dedent(`\\\n  foo\n`)".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn warn_unsupported_tag() {
+        assert_eq!(
+            diagnose(
+                r#"import { dedent } from "@qnighy/dedent";
+(cond ? dedent : foo)`foo`;
+(foo(), dedent)`foo`;
+"#
+            ),
+            [
+                (
+                    Severity::Warning,
+                    "dedent/unsupported-tag",
+                    "dedent".to_owned()
+                ),
+                (
+                    Severity::Warning,
+                    "dedent/unsupported-tag",
+                    "dedent".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn warn_unsupported_namespace_tag() {
+        assert_eq!(
+            diagnose(
+                r#"import * as m from "@qnighy/dedent";
+(cond ? m.dedent : foo)`foo`;
+"#
+            ),
+            [(
+                Severity::Warning,
+                "dedent/unsupported-tag",
+                "m.dedent".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn ignore_other_namespace_members() {
+        assert_eq!(
+            diagnose(
+                r#"import * as m from "@qnighy/dedent";
+m.other`foo`;
+(cond ? m.html : foo)`foo`;
+"#
+            ),
+            []
+        );
+    }
+
+    #[test]
+    fn ignore_non_dedent_tags() {
+        assert_eq!(
+            diagnose(
+                r#"import { dedent } from "@qnighy/dedent";
+foo(dedent)`foo`;
+foo("foo");
+"#
+            ),
+            []
+        );
+    }
+
+    fn no_runtime() -> Config {
+        Config {
+            no_runtime: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn no_runtime_accepts_fully_transformed_code() {
+        assert_eq!(
+            diagnose_with_config(
+                &no_runtime(),
+                r#"import { dedent } from "@qnighy/dedent";
+import * as m from "@qnighy/dedent";
+dedent`foo`;
+m.dedent(foo)`foo`;
+foo.dedent;
+({ dedent: 1 });
+"#
+            ),
+            []
+        );
+    }
+
+    #[test]
+    fn no_runtime_rejects_residual_references() {
+        assert_eq!(
+            diagnose_with_config(
+                &no_runtime(),
+                r#"import { dedent } from "@qnighy/dedent";
+const m = require("@qnighy/dedent");
+dedent`foo`;
+arr.map(dedent);
+m.dedent(...tags)`foo`;
+dedent`\9`;
+"#
+            ),
+            [
+                (Severity::Warning, "dedent/wrapper-spread", "...".to_owned()),
+                (Severity::Warning, "dedent/invalid-escape", "\\9".to_owned()),
+                (
+                    Severity::Error,
+                    "dedent/runtime-reference",
+                    "dedent".to_owned()
+                ),
+                (Severity::Error, "dedent/runtime-reference", "m".to_owned()),
+                (
+                    Severity::Error,
+                    "dedent/runtime-reference",
+                    "\"@qnighy/dedent\"".to_owned()
+                ),
+                (
+                    Severity::Error,
+                    "dedent/runtime-reference",
+                    "\"@qnighy/dedent\"".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_runtime_rejects_residual_imports() {
+        assert_eq!(
+            diagnose_with_config(
+                &no_runtime(),
+                r#"import { dedent } from "@qnighy/dedent";
+import { dedentRaw } from "@qnighy/dedent";
+import "@qnighy/dedent";
+export * from "@qnighy/dedent";
+const m = require("@qnighy/dedent");
+import { foo } from "foo";
+"#
+            ),
+            [
+                (
+                    Severity::Error,
+                    "dedent/runtime-reference",
+                    "\"@qnighy/dedent\"".to_owned()
+                ),
+                (
+                    Severity::Error,
+                    "dedent/runtime-reference",
+                    "\"@qnighy/dedent\"".to_owned()
+                ),
+                (
+                    Severity::Error,
+                    "dedent/runtime-reference",
+                    "\"@qnighy/dedent\"".to_owned()
+                ),
+                (
+                    Severity::Error,
+                    "dedent/runtime-reference",
+                    "\"@qnighy/dedent\"".to_owned()
+                ),
+                (
+                    Severity::Error,
+                    "dedent/runtime-reference",
+                    "\"@qnighy/dedent\"".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_runtime_is_off_by_default() {
+        assert_eq!(
+            diagnose(
+                r#"import { dedent } from "@qnighy/dedent";
+arr.map(dedent);
+"#
+            ),
+            []
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_output_mode {
+    use super::*;
+    use swc_core::ecma::transforms::testing::test;
+
+    fn output(output: OutputMode) -> MainVisitor {
+        MainVisitor::with_config(&Config {
+            output,
+            ..Default::default()
+        })
+    }
+
+    test!(
+        Default::default(),
+        |_| as_folder(output(OutputMode::Es5)),
+        stringify_substitutions_before_throwing_in_es5,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent`\
+  ${foo()}
+  \u{110000}${bar}
+`;
+"#,
+        r#"const text = function() {
+    "".concat(arguments[0]);
+    throw new SyntaxError("Undefined Unicode code-point");
+}(foo(), bar);"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(output(OutputMode::String)),
+        emit_string_literals_for_constant_templates,
+        r#"import { dedent } from "@qnighy/dedent";
+const text1 = dedent`\
+  foo
+    bar\u{41}
+`;
+const text2 = dedent`\
+  foo
+  ${bar}
+`;
+"#,
+        r#"const text1 = "foo\n  barA\n";
+const text2 = `\
+foo
+${bar}
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(output(OutputMode::String)),
+        join_escaped_surrogate_pairs,
+        r#"import { dedent } from "@qnighy/dedent";
+const text1 = dedent`\
+  \uD83D\uDE00
+`;
+const text2 = dedent`\
+  \uD83D
+`;
+"#,
+        r#"const text1 = "😀\n";
+const text2 = `\
+\uD83D
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(output(OutputMode::String)),
+        keep_tagged_templates_in_string_mode,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent(tag)`\
+  foo
+`;
+"#,
+        r#"const text = tag`\
+foo
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(output(OutputMode::Es5)),
+        emit_concat_calls_for_templates_with_substitutions,
+        r#"import { dedent } from "@qnighy/dedent";
+const text1 = dedent`\
+  foo
+`;
+const text2 = dedent`\
+  foo ${bar}
+  ${baz}
+`;
+const text3 = dedent`${foo}`;
+"#,
+        r#"const text1 = "foo\n";
+const text2 = "foo ".concat(bar, "\n").concat(baz, "\n");
+const text3 = "".concat(foo);"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(output(OutputMode::Es5)),
+        emit_concat_calls_line_by_line,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent`\
+  foo
+    bar ${baz}
+  ${1}
+  qux
+`;
+"#,
+        r#"const text = "foo\n".concat("  bar ", baz, "\n1\nqux\n");"#
+    );
+}
+
+#[cfg(test)]
+mod test_constant_folding {
+    use super::*;
+    use crate::test_utils::with_resolver;
+    use swc_core::ecma::transforms::testing::test;
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        fold_literal_substitutions,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent`\
+  Version: ${"1.2"}
+  Count: ${3} ${-1.5} ${1e21} ${10n}
+  Flags: ${true} ${null} ${undefined} ${void 0}
+  Rest: ${rest}
+`;
+"#,
+        r#"const text = `\
+Version: 1.2
+Count: 3 -1.5 1e+21 10
+Flags: true null undefined undefined
+Rest: ${rest}
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        escape_folded_strings,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent`\
+  ${"`a\\b`"} $${"{c}"} ${"${d}"}
+`;
+"#,
+        r#"const text = `\
+\`a\\b\` $\{c} \${d}
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        keep_lone_surrogates,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent`x${"\uD800"}y${"\uD83D\uDE00"}${`\uDC00`}`;
+"#,
+        r#"const text = `x${"\uD800"}y😀${`\uDC00`}`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        dedent_before_folding,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent`\
+  foo
+  ${"\n    bar"}
+`;
+"#,
+        r#"const text = `\
+foo
+
+    bar
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        keep_substitutions_in_tagged_templates,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent(tag)`\
+  Version: ${"1.2"}
+`;
+"#,
+        r#"const text = tag`\
+Version: ${"1.2"}
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::with_config(&Config {
+            output: OutputMode::String,
+            ..Default::default()
+        })),
+        emit_string_literals_after_folding,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent`\
+  Version: ${"1.2"}
+`;
+"#,
+        r#"const text = "Version: 1.2\n";"#
+    );
+
+    test!(
+        Default::default(),
+        |_| with_resolver(),
+        keep_shadowed_undefined,
+        r#"import { dedent } from "@qnighy/dedent";
+function f(undefined) {
+  return dedent`\
+    ${undefined}
+  `;
+}
+const text = dedent`\
+  ${undefined}
+`;
+"#,
+        r#"function f(undefined) {
+    return `\
+${undefined}
+`;
+}
+const text = `\
+undefined
+`;"#
+    );
+}
+
+#[cfg(test)]
+mod test_reindent_substitutions {
+    use super::*;
+    use swc_core::ecma::transforms::testing::test;
+
+    fn reindent() -> MainVisitor {
+        MainVisitor::with_config(&Config {
+            reindent_substitutions: true,
+            ..Default::default()
+        })
+    }
+
+    test!(
+        Default::default(),
+        |_| as_folder(reindent()),
+        reindent_nested_dedent_templates,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent`\
+  function f() {
+    ${dedent`\
+      if (x) {
+        return;
+      }
+    `}
+  }
+`;
+"#,
+        r#"const text = `\
+function f() {
+  if (x) {
+    return;
+  }
+
+}
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(reindent()),
+        reindent_multi_line_strings,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent`\
+  - ${"a\n\nb"}
+    ${"c\nd"} ${"e\nf"}
+`;
+"#,
+        r#"const text = `\
+- a
+
+  b
+  c
+  d e
+    f
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::with_config(&Config {
+            reindent_substitutions: true,
+            tab_width: std::num::NonZeroUsize::new(4),
+            ..Default::default()
+        })),
+        reindent_by_columns,
+        "import { dedent } from \"@qnighy/dedent\";\nconst text = dedent`\\\n\tlist:\n\t\t-\t${\"a\\nb\"}\n`;\n",
+        "const text = `\\\nlist:\n\t-\ta\n\t    b\n`;"
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(reindent()),
+        do_not_reindent_lone_surrogates,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent`\
+  list:
+    ${"a\n\uD800"}
+`;
+"#,
+        r#"const text = `\
+list:
+  ${"a\n\uD800"}
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::new()),
+        do_not_reindent_by_default,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent`\
+  list:
+    ${"a\nb"}
+`;
+"#,
+        r#"const text = `\
+list:
+  a
+b
+`;"#
+    );
+}
+
+#[cfg(test)]
+mod test_tab_width {
+    use super::*;
+    use std::num::NonZeroUsize;
+    use swc_core::ecma::transforms::testing::test;
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::with_config(&Config {
+            tab_width: NonZeroUsize::new(4),
+            ..Default::default()
+        })),
+        dedent_mixed_indentation_by_columns,
+        "import { dedent } from \"@qnighy/dedent\";\nconst text = dedent`\\\n\tfoo\n    \tbar\n  \tbaz\n`;\n",
+        "const text = `\\\nfoo\n\tbar\nbaz\n`;"
+    );
+}
+
+#[cfg(test)]
+mod test_whitespace {
+    use super::*;
+    use qnighy_dedent::Whitespace;
+    use swc_core::ecma::transforms::testing::test;
+
+    test!(
+        Default::default(),
+        |_| as_folder(MainVisitor::with_config(&Config {
+            whitespace: Whitespace::EcmaScript,
+            ..Default::default()
+        })),
+        dedent_ideographic_spaces,
+        "import { dedent } from \"@qnighy/dedent\";\nconst text = dedent`\\\n\u{3000}\u{3000}foo\n\u{3000}\u{3000}\u{3000}bar\n`;\n",
+        "const text = `\\\nfoo\n\u{3000}bar\n`;"
+    );
+}
+
+#[cfg(test)]
+mod test_normalize_escapes {
+    use super::*;
+    use swc_core::ecma::transforms::testing::test;
+
+    fn normalize() -> MainVisitor {
+        MainVisitor::with_config(&Config {
+            normalize_escapes: true,
+            ..Default::default()
+        })
+    }
+
+    test!(
+        Default::default(),
+        |_| as_folder(normalize()),
+        normalize_redundant_escapes,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent`\
+  \'foo\' \%\x41\u{0000042}
+  ${x}\$\{\}\`
+`;
+"#,
+        r#"const text = `'foo' %AB
+${x}\${}\`
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| as_folder(normalize()),
+        keep_tagged_template_raw,
+        r#"import { dedent } from "@qnighy/dedent";
+const text = dedent(tag)`\
+  \'foo\'
+`;
+"#,
+        r#"const text = tag`\
+\'foo\'
+`;"#
+    );
+}
+
+#[cfg(test)]
+mod test_string_raw_folding {
+    use crate::test_utils::with_resolver;
+    use swc_core::ecma::transforms::testing::test;
+
+    test!(
+        Default::default(),
+        |_| with_resolver(),
+        fold_dedent_string_raw,
+        r#"import { dedent } from "@qnighy/dedent";
+const path = dedent(String.raw)`
+  ${"C:"}\Users\me${1}
+`;
+"#,
+        r#"const path = "\nC:\\Users\\me1\n";"#
+    );
+
+    test!(
+        Default::default(),
+        |_| with_resolver(),
+        keep_string_raw_with_non_constant_substitutions,
+        r#"import { dedent } from "@qnighy/dedent";
+const path = dedent(String.raw)`
+  C:\Users\me ${user}
+`;
+"#,
+        r#"const path = (0, String.raw)`
+C:\Users\me ${user}
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| with_resolver(),
+        keep_string_raw_with_lone_surrogates,
+        r#"import { dedent } from "@qnighy/dedent";
+const path = dedent(String.raw)`
+  C:\Users\me${"\uD800"}
+`;
+"#,
+        r#"const path = (0, String.raw)`
+C:\Users\me${"\uD800"}
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| with_resolver(),
+        keep_shadowed_string_raw,
+        r#"import { dedent } from "@qnighy/dedent";
+const String = { raw: tag };
+const path = dedent(String.raw)`\
+  C:\Users
+`;
+"#,
+        r#"const String = { raw: tag };
+const path = (0, String.raw)`\
+C:\Users
+`;"#
+    );
+
+    test!(
+        Default::default(),
+        |_| with_resolver(),
+        keep_other_tags,
+        r#"import { dedent } from "@qnighy/dedent";
+const path = dedent(String.other)`\
+  foo
+`;
+"#,
+        r#"const path = (0, String.other)`\
+foo
+`;"#
+    );
+}
+
+#[cfg(test)]
+mod test_spans {
+    use super::*;
+    use swc_core::common::SourceMapper;
+    use swc_core::ecma::transforms::testing::Tester;
+
+    /// Collects the spans of the template elements and the string literals.
+    #[derive(Default)]
+    struct LiteralSpans(Vec<Span>);
+
+    impl Visit for LiteralSpans {
+        fn visit_tpl_element(&mut self, n: &TplElement) {
+            self.0.push(n.span);
+        }
+        fn visit_str(&mut self, n: &Str) {
+            self.0.push(n.span);
+        }
+    }
+
+    /// Returns the original snippets of the literals in the transformed code.
+    fn literal_snippets(config: &Config, src: &str) -> Vec<String> {
+        Tester::run(|tester| {
+            let mut module = tester.parse_module("input.js", src)?;
+            module.visit_mut_with(&mut MainVisitor::with_config(config));
+            let mut v = LiteralSpans::default();
+            module.visit_with(&mut v);
+            Ok(v.0
+                .iter()
+                .map(|&span| tester.cm.span_to_snippet(span).unwrap())
+                .collect())
+        })
+    }
+
+    fn output(output: OutputMode) -> Config {
+        Config {
+            output,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn keep_the_spans_of_the_original_quasis() {
+        assert_eq!(
+            literal_snippets(
+                &Config::default(),
+                r#"import { dedent } from "@qnighy/dedent";
+dedent`\
+    foo ${x}
+      bar
+    ${y} baz
+`;
+"#
+            ),
+            ["\\\n    foo ", "\n      bar\n    ", " baz\n"]
+        );
+    }
+
+    #[test]
+    fn span_the_folded_substitutions() {
+        assert_eq!(
+            literal_snippets(
+                &Config::default(),
+                r#"import { dedent } from "@qnighy/dedent";
+dedent`\
+  foo ${1} bar ${x}
+`;
+"#
+            ),
+            ["\\\n  foo ${1} bar ", "\n"]
+        );
+    }
+
+    #[test]
+    fn span_the_template_in_string_output() {
+        assert_eq!(
+            literal_snippets(
+                &output(OutputMode::String),
+                r#"import { dedent } from "@qnighy/dedent";
+dedent`\
+  foo
+`;
+"#
+            ),
+            ["`\\\n  foo\n`"]
+        );
+    }
+
+    #[test]
+    fn span_each_line_in_es5_output() {
+        assert_eq!(
+            literal_snippets(
+                &output(OutputMode::Es5),
+                r#"import { dedent } from "@qnighy/dedent";
+dedent`\
+  foo ${x}
+  bar
+`;
+"#
+            ),
+            ["foo ", "\n", "bar\n"]
+        );
+    }
+
+    #[test]
+    fn span_the_folded_substitutions_in_es5_output() {
+        assert_eq!(
+            literal_snippets(
+                &output(OutputMode::Es5),
+                r#"import { dedent } from "@qnighy/dedent";
+dedent`\
+  foo
+    bar ${x}
+  baz ${1}
+  qux
+`;
+"#
+            ),
+            ["foo\n", "  bar ", "\n  baz ${1}\n  qux\n"]
+        );
+    }
+
+    #[test]
+    fn span_each_line_after_normalized_newlines() {
+        assert_eq!(
+            literal_snippets(
+                &output(OutputMode::Es5),
+                "import { dedent } from \"@qnighy/dedent\";\r\ndedent`\\\r\n  foo\r\n  bar ${x}`;\r\n"
+            ),
+            ["foo\r\n", "bar "]
+        );
+    }
+}